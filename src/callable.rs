@@ -20,25 +20,37 @@ pub mod definitions {
     use crate::statement::Stmt;
     use crate::token::Token;
 
+    // A class's name, superclass, instance methods, and static methods.
+
     #[derive(Clone, Debug)]
-    pub struct Class(pub Rc<Token>, pub Option<Rc<Token>>, pub Vec<Function>);
+    pub struct Class(
+        pub Rc<Token>, pub Option<Rc<Token>>,
+        pub Vec<Function>, pub Vec<Function>
+    );
+
+    // A function's name, parameters, body, and whether it's a getter. A getter
+    // is a method without a parameter list that runs when it's accessed.
 
     #[derive(Clone, Debug)]
-    pub struct Function(pub Rc<Token>, pub Rc<Vec<Token>>, pub Rc<Vec<Stmt>>);
+    pub struct Function(
+        pub Rc<Token>, pub Rc<Vec<Token>>,
+        pub Rc<Vec<Stmt>>, pub bool
+    );
 }
 
 type Methods = FxHashMap<String, Function>;
 
 #[derive(Clone, Debug)]
-pub struct Class(Rc<Token>, Option<Rc<Class>>, Rc<Methods>);
+pub struct Class(Rc<Token>, Option<Rc<Class>>, Rc<Methods>, Rc<Methods>);
 
 impl Class {
     pub fn new(
         name: Rc<Token>,
         parent: Option<Rc<Class>>,
-        methods: Rc<Methods>
+        methods: Rc<Methods>,
+        statics: Rc<Methods>
     ) -> Class {
-        Class(name, parent, methods)
+        Class(name, parent, methods, statics)
     }
 
     pub fn erase(self) -> Callable {
@@ -54,25 +66,35 @@ impl Class {
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
+        paren: &Token,
         arguments: Vec<Object>
     ) -> Result<Object, int::Unwind> {
         let instance = Instance::new(self.clone());
 
         if let Some(initializer) = self.find_method("init") {
-            initializer.bind(&instance).call(interpreter, arguments)
+            initializer.bind(&instance).call(interpreter, paren, arguments)
         } else {
             Ok(Object::Instance(instance))
         }
     }
 
     pub fn find_method(&self, name: &str) -> Option<Function> {
-        let Class(_, parent, methods) = self;
+        let Class(_, parent, methods, _) = self;
 
         methods.get(name).map_or_else(
             || parent.as_ref().and_then(|parent| parent.find_method(name)),
             |method| Some(method.clone())
         )
     }
+
+    pub fn find_static(&self, name: &str) -> Option<Function> {
+        let Class(_, parent, _, statics) = self;
+
+        statics.get(name).map_or_else(
+            || parent.as_ref().and_then(|parent| parent.find_static(name)),
+            |method| Some(method.clone())
+        )
+    }
 }
 
 impl fmt::Display for Class {
@@ -126,10 +148,11 @@ impl Function {
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
+        paren: &Token,
         arguments: Vec<Object>,
     ) -> Result<Object, int::Unwind> {
         let Function(
-            def::Function(_, parameters, body),
+            def::Function(name, parameters, body, _),
             closure, is_initializer
         ) = self;
 
@@ -144,11 +167,14 @@ impl Function {
             env::define(&mut local, parameter.to_name().1, argument);
         }
 
-        let result = interpreter.execute_block(body, env::copy(&local));
+        let result = interpreter.in_frame(
+            name.to_name().1, paren,
+            |interpreter| interpreter.execute_block(body, env::copy(&local))
+        );
 
         match result {
             // The programmer returned with an explicit `return` keyword.
-            Err(int::Unwind::Return(_, object)) =>
+            Err(int::Unwind::Return(object)) =>
                 if *is_initializer {
                     Ok(env::get_at(closure, 0, "this"))
                 } else { Ok(object) },
@@ -162,6 +188,11 @@ impl Function {
         }
     }
 
+    pub fn is_getter(&self) -> bool {
+        let Function(def::Function(.., is_getter), ..) = self;
+        *is_getter
+    }
+
     pub fn bind(&self, instance: &Instance) -> Function {
         let Function(definition, closure, is_initializer) = self;
         let mut with_this = env::new_with_enclosing(closure);
@@ -179,10 +210,12 @@ impl fmt::Display for Function {
 
 impl cmp::PartialEq for Function {
     fn eq(&self, other: &Function) -> bool {
-        let Function(def::Function(name, ..), closure, _) = self;
-        let Function(def::Function(other_name, ..), other_closure, _) = other;
+        let Function(def::Function(name, _, body, _), closure, _) = self;
+        let Function(def::Function(other_name, _, other_body, _), other_closure, _) = other;
 
         name.token_type == other_name.token_type
+            // Anonymous functions share a name.
+            && Rc::ptr_eq(body, other_body)
             // This is a hack to make rlox behave like the reference Java
             // implementation. Bound methods are equal if they share the
             // same binding site, not the same object.
@@ -190,34 +223,82 @@ impl cmp::PartialEq for Function {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Fixed(u8),
+    Variadic,
+}
+
+// How a native stops a script early. Most natives only fail with a runtime
+// error, but 'exit' ends the script on purpose.
+
 #[derive(Clone, Debug, PartialEq)]
-pub enum Native {
-    Clock
+pub enum Halt {
+    Error(String),
+    Exit(i32),
+}
+
+pub type NativeFunction = dyn Fn(Vec<Object>) -> Result<Object, Halt>;
+
+// A native function is a Rust closure wearing a Lox name. Built-in natives and
+// natives registered by a host program are represented the same way. The
+// closure reports a runtime error by returning its message, which is pinned to
+// the call site by the interpreter.
+
+#[derive(Clone)]
+pub struct Native {
+    name: Rc<str>,
+    arity: Arity,
+    function: Rc<NativeFunction>,
 }
 
 impl Native {
+    pub fn new<F>(name: &str, arity: Arity, function: F) -> Native
+        where F: Fn(Vec<Object>) -> Result<Object, String> + 'static
+    {
+        Native::halting(
+            name, arity,
+            move |arguments| function(arguments).map_err(Halt::Error)
+        )
+    }
+
+    pub fn halting<F>(name: &str, arity: Arity, function: F) -> Native
+        where F: Fn(Vec<Object>) -> Result<Object, Halt> + 'static
+    {
+        Native {
+            name: Rc::from(name),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+
     pub fn erase(self) -> Callable {
         Callable::Native(self)
     }
 
-    pub fn arity(&self) -> u8 {
-        match self {
-            Native::Clock => 0
-        }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.arity
     }
 
     pub fn call(
         &self,
-        _: &Interpreter,
-        _: Vec<Object>,
+        paren: &Token,
+        arguments: Vec<Object>,
     ) -> Result<Object, int::Unwind> {
-        match self {
-            Native::Clock => call_clock()
-        }
+        (self.function)(arguments).map_err(|halt| match halt {
+            Halt::Error(message) =>
+                int::Unwind::Error(int::Error::new(paren, message)),
+            Halt::Exit(code) =>
+                int::Unwind::Exit(code),
+        })
     }
 }
 
-fn call_clock() -> Result<Object, int::Unwind> {
+pub fn clock(_: Vec<Object>) -> Result<Object, String> {
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH);
 
@@ -227,12 +308,24 @@ fn call_clock() -> Result<Object, int::Unwind> {
     ))
 }
 
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Native({:?}, {:?})", self.name, self.arity)
+    }
+}
+
 impl fmt::Display for Native {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "<native fn>")
     }
 }
 
+impl cmp::PartialEq for Native {
+    fn eq(&self, other: &Native) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Callable {
     Class(Class),
@@ -241,26 +334,27 @@ pub enum Callable {
 }
 
 impl Callable {
-    pub fn arity(&self) -> u8 {
+    pub fn arity(&self) -> Arity {
         match self {
-            Callable::Class(class) => class.arity(),
+            Callable::Class(class) => Arity::Fixed(class.arity()),
             Callable::Native(native) => native.arity(),
-            Callable::Function(function) => function.arity(),
+            Callable::Function(function) => Arity::Fixed(function.arity()),
         }
     }
 
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
+        paren: &Token,
         arguments: Vec<Object>
     ) -> Result<Object, int::Unwind> {
         match self {
             Callable::Class(class) =>
-                class.call(interpreter, arguments),
+                class.call(interpreter, paren, arguments),
             Callable::Function(function) =>
-                function.call(interpreter, arguments),
+                function.call(interpreter, paren, arguments),
             Callable::Native(native) =>
-                native.call(interpreter, arguments),
+                native.call(paren, arguments),
         }
     }
 }
@@ -87,6 +87,24 @@ pub fn assign_at(local: &Environment, distance: usize, name: &str, object: &Obje
     bindings.objects.insert(name.to_string(), Object::clone(object));
 }
 
+pub fn root(local: &Environment) -> Environment {
+    // The outermost environment of a chain is the global scope of the file
+    // its code came from.
+
+    let mut current = copy(local);
+
+    loop {
+        let parent = match current.borrow().enclosing {
+            Some(ref next) => copy(next),
+            None => break,
+        };
+
+        current = parent;
+    }
+
+    current
+}
+
 fn ancestor(local: &Environment, distance: usize) -> Environment {
     let mut current = copy(local);
 
@@ -116,7 +134,7 @@ mod tests {
 
     #[test]
     fn look_in_enclosing() {
-        let value = Object::Number(Rc::new(4 as f64));
+        let value = Object::Number(4.0);
 
         let mut local = new();
         let mut enclosing = new();
@@ -129,6 +147,16 @@ mod tests {
 
         assert_eq!(get(&local, "waffle").unwrap(), value);
     }
+
+    #[test]
+    fn find_root() {
+        let global = new();
+        let enclosing = new_with_enclosing(&global);
+        let local = new_with_enclosing(&enclosing);
+
+        assert!(Rc::ptr_eq(&root(&local), &global));
+        assert!(Rc::ptr_eq(&root(&global), &global));
+    }
 }
 
 // [1]
@@ -1,27 +1,379 @@
-use crate::token::Token;
+use std::fmt;
+
+use crate::token::{Span, Token};
 use crate::token_type::TokenType as TT;
 
-pub enum LoxError {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
     Scan, Parse, Resolve, Interpret,
 }
 
-pub fn report(line: usize, location: &str, message: &str) {
-    eprintln!("[line {}] Error{}: {}", line, location, message);
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Scan      => "scan",
+            Phase::Parse     => "parse",
+            Phase::Resolve   => "resolve",
+            Phase::Interpret => "interpret",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        // From FreeBSD's 'sysexits.h'. Bad source code is a data error and
+        // a runtime error is an internal software error.
+
+        match self {
+            Phase::Scan      => 65,
+            Phase::Parse     => 65,
+            Phase::Resolve   => 65,
+            Phase::Interpret => 70,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error, Warning,
+}
+
+// What a diagnostic blames. Scanner errors blame the line, the parser can run
+// off the end of the source, and everything else blames a token.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Location {
+    Line,
+    End,
+    Lexeme(String),
+}
+
+// A secondary annotation on a diagnostic, like where a variable was declared
+// the first time. Only the rich style shows labels.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+impl Label {
+    pub fn new(token: &Token, message: &str) -> Label {
+        Label {
+            message: message.to_string(),
+            line: token.line,
+            column: token.column,
+            span: token.span,
+        }
+    }
+}
+
+// Where a runtime error happened and how the program got there, innermost
+// first. The last frame is always the top level of the script. A frame whose
+// line is in an imported file names that file.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+    pub file: Option<String>,
+}
+
+impl Frame {
+    pub fn new(function: &str, line: usize) -> Frame {
+        Frame { function: function.to_string(), line, file: None }
+    }
+
+    pub fn in_file(mut self, file: &str) -> Frame {
+        self.file = Some(file.to_string());
+        self
+    }
+}
+
+// The scanner, parser, resolver, and interpreter collect diagnostics instead
+// of printing them. Whoever drives the pipeline decides where they go. Their
+// Display matches the reference implementation's output, which the test suite
+// depends on. A diagnostic from an imported file names that file, and its
+// line, column, span, and labels are in that file.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub phase: Phase,
+    pub message: String,
+    pub location: Location,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub trace: Vec<Frame>,
+    pub file: Option<String>,
+}
+
+pub type Diagnostics = Vec<Diagnostic>;
+
+impl Diagnostic {
+    pub fn new(
+        phase: Phase,
+        message: &str,
+        location: Location,
+        line: usize, column: usize, span: Span
+    ) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            phase,
+            message: message.to_string(),
+            location,
+            line,
+            column,
+            span,
+            labels: Vec::new(),
+            trace: Vec::new(),
+            file: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: Label) -> Diagnostic {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_trace(mut self, trace: Vec<Frame>) -> Diagnostic {
+        self.trace = trace;
+        self
+    }
+
+    pub fn in_file(mut self, file: &str) -> Diagnostic {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    pub fn to_json(&self, file: &str) -> String {
+        // One line of JSON, for tools that would rather not parse the plain
+        // style. The field order is stable. The file is the source being run
+        // unless the diagnostic is from a file it imported.
+
+        let severity = match self.severity {
+            Severity::Error   => "error",
+            Severity::Warning => "warning",
+        };
+
+        format!(
+            "{{\"file\":{},\"line\":{},\"column\":{},\"start\":{},\"end\":{},\
+             \"phase\":\"{}\",\"severity\":\"{}\",\"message\":{},\"exit_code\":{}}}",
+            json_string(self.file.as_deref().unwrap_or(file)),
+            self.line, self.column, self.span.start, self.span.end,
+            self.phase.name(), severity,
+            json_string(&self.message),
+            self.phase.exit_code()
+        )
+    }
+
+    pub fn scanner_error(
+        line: usize, column: usize, span: Span,
+        message: &str
+    ) -> Diagnostic {
+        Diagnostic::new(Phase::Scan, message, Location::Line, line, column, span)
+    }
+
+    pub fn parse_error(token: &Token, message: &str) -> Diagnostic {
+        Diagnostic::at_token(Phase::Parse, token, message)
+    }
+
+    pub fn resolve_error(token: &Token, message: &str) -> Diagnostic {
+        Diagnostic::at_token(Phase::Resolve, token, message)
+    }
+
+    pub fn runtime_error(token: &Token, message: &str) -> Diagnostic {
+        Diagnostic::at_token(Phase::Interpret, token, message)
+    }
+
+    fn at_token(phase: Phase, token: &Token, message: &str) -> Diagnostic {
+        let location = if token.token_type == TT::EndOfFile {
+            Location::End
+        } else {
+            Location::Lexeme(token.lexeme.clone())
+        };
+
+        Diagnostic::new(
+            phase, message, location,
+            token.line, token.column, token.span
+        )
+    }
+}
+
+const TRACE_LENGTH: usize = 16;
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.phase == Phase::Interpret {
+            // An error outside of any function doesn't have a trace worth
+            // printing. The first line of a trace is where the error happened,
+            // so the reference test suite is still satisfied.
+
+            let (script, frames) = match self.trace.split_last() {
+                Some(split) => split,
+                None => return write!(
+                    f, "{}\n[{}]",
+                    self.message, at_line(self.line, &self.file)
+                ),
+            };
+
+            write!(f, "{}", self.message)?;
+
+            // A runaway recursion would print hundreds of identical frames.
+            // Keep the innermost ones and the script.
+
+            let elided = self.trace.len().saturating_sub(TRACE_LENGTH);
+
+            for frame in &frames[..frames.len() - elided] {
+                let line = at_line(frame.line, &frame.file);
+                write!(f, "\n[{}] in {}", line, frame.function)?;
+            }
+
+            if elided > 0 {
+                write!(f, "\n... {} more", elided)?;
+            }
+
+            let line = at_line(script.line, &script.file);
+            write!(f, "\n[{}] in {}", line, script.function)?;
+
+            return Ok(());
+        }
+
+        let severity = match self.severity {
+            Severity::Error   => "Error",
+            Severity::Warning => "Warning",
+        };
+
+        let location = match self.location {
+            Location::Line => String::new(),
+            Location::End => " at end".to_string(),
+            Location::Lexeme(ref lexeme) => format!(" at '{}'", lexeme),
+        };
+
+        write!(
+            f, "[{}] {}{}: {}",
+            at_line(self.line, &self.file), severity, location, self.message
+        )
+    }
+}
+
+fn at_line(line: usize, file: &Option<String>) -> String {
+    // The source being run goes without saying, like in the reference
+    // implementation.
+
+    match file {
+        Some(file) => format!("line {} of {}", line, file),
+        None => format!("line {}", line),
+    }
+}
+
+// How diagnostics are written out. The plain style is one or two lines per
+// diagnostic, exactly like the reference implementation. The rich style
+// follows the plain report with the offending source lines, underlining the
+// culprit with carets and any labels with dashes.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    Plain, Rich,
+}
+
+pub fn render(diagnostic: &Diagnostic, source: &str, style: Style) -> String {
+    let mut rendered = diagnostic.to_string();
+
+    if style == Style::Plain { return rendered; }
+
+    let mut annotations = vec![(diagnostic.span, '^', "")];
+
+    for label in &diagnostic.labels {
+        annotations.push((label.span, '-', &label.message));
+    }
+
+    // Skip anything that isn't from this source, like a stale diagnostic.
+    annotations.retain(|(span, ..)|
+        span.start <= span.end && span.end <= source.len()
+            && source.is_char_boundary(span.start)
+            && source.is_char_boundary(span.end)
+    );
+
+    let width = annotations.iter().map(
+        |(span, ..)| line_number(source, span.start).to_string().len()
+    ).max().unwrap_or(0);
+
+    for (span, marker, message) in annotations {
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..].find('\n').map_or(
+            source.len(),
+            |i| span.start + i
+        );
+
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        // Keep tabs so the underline lines up with the text above it.
+        let indent: String = source[line_start..span.start].chars().map(
+            |c| if c == '\t' { '\t' } else { ' ' }
+        ).collect();
+
+        // A span that runs onto later lines is only underlined on its first.
+        let length = source[span.start..span.end.min(line_end)].chars().count();
+        let underline = marker.to_string().repeat(length.max(1));
+
+        let gutter = " ".repeat(width);
+        let number = line_number(source, span.start);
+
+        rendered.push_str(&format!("\n{} |", gutter));
+        rendered.push_str(&format!("\n{:>w$} | {}", number, text, w = width));
+        rendered.push_str(&format!("\n{} | {}{}", gutter, indent, underline));
+
+        if !message.is_empty() {
+            rendered.push(' ');
+            rendered.push_str(message);
+        }
+    }
+
+    rendered
 }
 
-pub fn scanner_error(line: usize, message: &str) {
-    report(line, "", message);
+fn line_number(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
 }
 
-pub fn parse_error(token: &Token, message: &str) {
-    if token.token_type == TT::EndOfFile {
-        report(token.line, " at end", message);
-    } else {
-        let location: String = format!(" at '{}'", token.lexeme);
-        report(token.line, &location, message);
+fn json_string(string: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in string.chars() {
+        match c {
+            '"'  => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
     }
+
+    quoted.push('"');
+    quoted
 }
 
-pub fn runtime_error(token: &Token, message: &str) {
-    eprintln!("{}\n[line {}]", message, token.line);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_line() {
+        let diagnostic = Diagnostic::new(
+            Phase::Parse, "Expect ';' after \"value\".",
+            Location::End, 2, 5, Span::new(12, 12)
+        );
+
+        assert_eq!(
+            diagnostic.to_json("scripts\\bagel.lox"),
+            "{\"file\":\"scripts\\\\bagel.lox\",\"line\":2,\"column\":5,\
+             \"start\":12,\"end\":12,\"phase\":\"parse\",\"severity\":\"error\",\
+             \"message\":\"Expect ';' after \\\"value\\\".\",\"exit_code\":65}"
+        );
+    }
 }
@@ -1,5 +1,6 @@
+use crate::callable::definitions as def;
 use crate::object::Object;
-use crate::token::Token;
+use crate::token::{Span, Token};
 
 #[derive(Debug)]
 pub enum Expr {
@@ -7,10 +8,16 @@ pub enum Expr {
     Binary(Box<Expr>, Token, Box<Expr>),
     Call(Box<Expr>, Token, Vec<Expr>),
     Get(Box<Expr>, Token),
-    Grouping(Box<Expr>),
-    Literal(Object),
+    Grouping(Span, Box<Expr>),
+    Index(Box<Expr>, Token, Box<Expr>),
+    Interpolation(Span, Vec<Expr>),
+    Lambda(Span, def::Function),
+    List(Span, Vec<Expr>),
+    Literal(Span, Object),
     Logical(Box<Expr>, Token, Box<Expr>),
+    Map(Span, Vec<(Expr, Token, Expr)>),
     Set(Box<Expr>, Token, Box<Expr>),
+    SetIndex(Box<Expr>, Token, Box<Expr>, Box<Expr>),
     Super(Token, Token),
     This(Token),
     Unary(Token, Box<Expr>),
@@ -23,9 +30,18 @@ pub trait Visitor<T> {
     fn visit_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> T;
     fn visit_get(&mut self, object: &Expr, name: &Token) -> T;
     fn visit_grouping(&mut self, expression: &Expr) -> T;
+    fn visit_index(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> T;
+    fn visit_interpolation(&mut self, parts: &[Expr]) -> T;
+    fn visit_lambda(&mut self, definition: &def::Function) -> T;
+    fn visit_list(&mut self, elements: &[Expr]) -> T;
     fn visit_literal(&mut self, object: &Object) -> T;
     fn visit_logical(&mut self, left: &Expr, operator: &Token, right: &Expr) -> T;
+    fn visit_map(&mut self, entries: &[(Expr, Token, Expr)]) -> T;
     fn visit_set(&mut self, object: &Expr, name: &Token, value: &Expr) -> T;
+    fn visit_set_index(
+        &mut self, object: &Expr, bracket: &Token,
+        index: &Expr, value: &Expr
+    ) -> T;
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> T;
     fn visit_this(&mut self, object: &Token) -> T;
     fn visit_unary(&mut self, operator: &Token, right: &Expr) -> T;
@@ -43,14 +59,26 @@ impl Expr {
                 visitor.visit_call(callee, paren, arguments),
             Expr::Get(object, name) =>
                 visitor.visit_get(object, name),
-            Expr::Grouping(expression) =>
+            Expr::Grouping(_, expression) =>
                 visitor.visit_grouping(expression),
-            Expr::Literal(object) =>
+            Expr::Index(object, bracket, index) =>
+                visitor.visit_index(object, bracket, index),
+            Expr::Interpolation(_, parts) =>
+                visitor.visit_interpolation(parts),
+            Expr::Lambda(_, definition) =>
+                visitor.visit_lambda(definition),
+            Expr::List(_, elements) =>
+                visitor.visit_list(elements),
+            Expr::Literal(_, object) =>
                 visitor.visit_literal(object),
             Expr::Logical(left, operator, right) =>
                 visitor.visit_logical(left, operator, right),
+            Expr::Map(_, entries) =>
+                visitor.visit_map(entries),
             Expr::Set(object, name, value) =>
                 visitor.visit_set(object, name, value),
+            Expr::SetIndex(object, bracket, index, value) =>
+                visitor.visit_set_index(object, bracket, index, value),
             Expr::Super(keyword, method) =>
                 visitor.visit_super(keyword, method),
             Expr::This(object) =>
@@ -61,4 +89,47 @@ impl Expr {
                 visitor.visit_variable(name),
         }
     }
+
+    pub fn span(&self) -> Span {
+        // The bytes of source an expression was parsed from.
+
+        match self {
+            Expr::Assignment(name, object) =>
+                name.span.to(object.span()),
+            Expr::Binary(left, _, right) =>
+                left.span().to(right.span()),
+            Expr::Call(callee, paren, _) =>
+                callee.span().to(paren.span),
+            Expr::Get(object, name) =>
+                object.span().to(name.span),
+            Expr::Grouping(span, _) =>
+                *span,
+            Expr::Index(object, bracket, _) =>
+                object.span().to(bracket.span),
+            Expr::Interpolation(span, _) =>
+                *span,
+            Expr::Lambda(span, _) =>
+                *span,
+            Expr::List(span, _) =>
+                *span,
+            Expr::Literal(span, _) =>
+                *span,
+            Expr::Logical(left, _, right) =>
+                left.span().to(right.span()),
+            Expr::Map(span, _) =>
+                *span,
+            Expr::Set(object, _, value) =>
+                object.span().to(value.span()),
+            Expr::SetIndex(object, _, _, value) =>
+                object.span().to(value.span()),
+            Expr::Super(keyword, method) =>
+                keyword.span.to(method.span),
+            Expr::This(this) =>
+                this.span,
+            Expr::Unary(operator, right) =>
+                operator.span.to(right.span()),
+            Expr::Variable(name) =>
+                name.span,
+        }
+    }
 }
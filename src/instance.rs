@@ -31,6 +31,10 @@ impl fmt::Display for Instance {
 }
 
 impl Instance {
+    pub fn class(&self) -> &Class {
+        &self.class
+    }
+
     pub fn get(&self, name: &str) -> Option<Object> {
         self.fields.borrow().get(name).map_or_else(
             || self.class.find_method(name).map(
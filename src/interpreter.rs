@@ -1,14 +1,26 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use rustc_hash::FxHashMap;
 
 use crate::callable::{self as call, definitions as def};
 use crate::environment as env;
-use crate::error;
+use crate::error::{Diagnostic, Diagnostics, Frame};
 use crate::expression::{self as expr, Expr};
+use crate::list::List;
+use crate::map::{self, Key, Map};
+use crate::module::{self, Module};
+use crate::prelude::{Input, Library};
 use crate::object::Object;
+use crate::parser::Parser;
 use crate::statement::{self as stmt, Stmt};
-use crate::token::Token;
+use crate::instance::Instance;
+use crate::token::{Span, Token};
 use crate::token_type::TokenType as TT;
 
 #[derive(Debug)]
@@ -20,7 +32,12 @@ pub struct Error {
 #[derive(Debug)]
 pub enum Unwind {
     Error(Error),
-    Return(Token, Object),
+    Return(Object),
+    Break,
+    Continue,
+    Exit(i32),
+    Throw(Box<Object>, Token),
+    Compile(Diagnostics),
 }
 
 impl Error {
@@ -29,45 +46,220 @@ impl Error {
     }
 }
 
+// A function call in progress, for stack traces. The call site is the line of
+// the call's closing parenthesis, in the file it's from.
+
+struct Call {
+    function: String,
+    line: usize,
+    file: usize,
+}
+
+// An imported file's name for diagnostics, its text for rendering them, and
+// where it is, for the files it imports.
+
+struct Source {
+    name: String,
+    text: Rc<str>,
+    path: PathBuf,
+}
+
+// How deep calls can nest before the interpreter gives up with a runtime
+// error instead of overflowing the native stack, and how much stack that
+// takes. Every Lox call costs a few kilobytes of Rust stack in a release build
+// and up to sixteen in a debug build, so the stack leaves room to spare for
+// either. The command line runs its session on a thread this big. A host that
+// can't spare the stack should lower the limit.
+
+pub const MAX_DEPTH: usize = 10_000;
+pub const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+// Every file has its own global environment. Natives live in another one that
+// all of them can see, behind their own globals.
+
 pub struct Interpreter {
-    global: env::Environment,
+    natives: env::Environment,
     local: env::Environment,
     resolutions: FxHashMap<usize, usize>,
+    stdout: Box<dyn Write>,
+    stdin: Input,
+    calls: Vec<Call>,
+    max_depth: usize,
+    trace: Vec<Frame>,
+    exit_code: Option<i32>,
+    error_class: call::Class,
+    identifier_key: usize,
+    files: Vec<PathBuf>,
+    sources: Vec<Source>,
+    modules: FxHashMap<PathBuf, Module>,
 }
 
 impl Interpreter {
-    pub fn new(resolutions: FxHashMap<usize, usize>) -> Interpreter {
-        let mut global = env::new();
+    pub fn new() -> Interpreter {
+        let mut interpreter = Interpreter::bare();
 
-        env::define(
-            &mut global, "clock",
-            &Object::Callable(call::Native::Clock.erase())
-        );
+        for library in &Library::DEFAULT {
+            interpreter.load(*library);
+        }
 
+        interpreter
+    }
+
+    pub fn bare() -> Interpreter {
         Interpreter {
-            global: env::copy(&global),
-            local: env::copy(&global),
-            resolutions,
+            natives: env::new(),
+            local: env::new(),
+            resolutions: FxHashMap::default(),
+            stdout: Box::new(io::stdout()),
+            stdin: Rc::new(RefCell::new(None)),
+            calls: Vec::new(),
+            max_depth: MAX_DEPTH,
+            trace: Vec::new(),
+            exit_code: None,
+            error_class: error_class(),
+            identifier_key: 0,
+            files: Vec::new(),
+            sources: Vec::new(),
+            modules: FxHashMap::default(),
         }
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), error::LoxError> {
+    pub fn load(&mut self, library: Library) {
+        for native in library.natives(&self.stdin) {
+            self.define_native(native);
+        }
+    }
+
+    pub fn set_stdout(&mut self, sink: Box<dyn Write>) {
+        self.stdout = sink;
+    }
+
+    pub fn set_stdin(&mut self, source: Box<dyn BufRead>) {
+        *self.stdin.borrow_mut() = Some(source);
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn define_native(&mut self, native: call::Native) {
+        let name = native.name().to_string();
+        let object = Object::Callable(native.erase());
+        env::define(&mut self.natives, &name, &object);
+    }
+
+    pub fn identifier_key(&self) -> usize {
+        self.identifier_key
+    }
+
+    pub fn set_identifier_key(&mut self, identifier_key: usize) {
+        // The scanner's next identifier key, shared with imported modules.
+        self.identifier_key = identifier_key;
+    }
+
+    pub fn set_path(&mut self, path: &Path) {
+        // The file being run, which imports are relative to.
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.files = vec![path];
+    }
+
+    pub fn source(&self, name: &str) -> Option<Rc<str>> {
+        // The text of an imported file, so a diagnostic from it can be shown
+        // against the right source.
+
+        self.sources.iter().find(|source| source.name == name).map(
+            |source| Rc::clone(&source.text)
+        )
+    }
+
+    fn file_name(&self, file: usize) -> Option<&str> {
+        // The source being run is file zero and doesn't need naming.
+        file.checked_sub(1).map(|index| self.sources[index].name.as_str())
+    }
+
+    fn in_file(&self, diagnostic: Diagnostic, token: &Token) -> Diagnostic {
+        match self.file_name(token.file) {
+            Some(name) => diagnostic.in_file(name),
+            None => diagnostic,
+        }
+    }
+
+    pub fn resolve(&mut self, resolutions: FxHashMap<usize, usize>) {
+        // Resolutions from earlier runs stay valid as long as the scanner
+        // never hands out the same identifier key twice.
+
+        self.resolutions.extend(resolutions);
+    }
+
+    pub fn interpret(
+        &mut self,
+        statements: Vec<Stmt>
+    ) -> Result<Option<Object>, Diagnostics> {
+        // Keep the value of a trailing expression statement so the prompt can
+        // echo it back to the programmer.
+
+        let mut last = None;
+
+        self.exit_code = None;
+        self.trace.clear();
+
         for statement in &statements {
-            if let Err(error) = self.execute(statement) {
-                match error {
-                    Unwind::Error(error) =>
-                        error::runtime_error(&error.token, &error.message),
-                    Unwind::Return(..) =>
-                        // A panic here indicates an error in the resolver or interpreter.
-                        panic!("uncaught return")
-                }
+            let result = match statement {
+                Stmt::Expression(_, expression) =>
+                    self.evaluate(expression).map(Some),
+                _ =>
+                    self.execute(statement).map(|_| None),
+            };
 
-                // A runtime error kills the interpreter.
-                return Err(error::LoxError::Interpret);
-            }
+            last = match result {
+                Ok(object) => object,
+                Err(error) => {
+                    // A runtime error kills the interpreter.
+                    return match error {
+                        Unwind::Error(error) => {
+                            let diagnostic = self.in_file(
+                                Diagnostic::runtime_error(
+                                    &error.token, &error.message
+                                ),
+                                &error.token
+                            );
+                            let trace = mem::take(&mut self.trace);
+                            Err(vec![diagnostic.with_trace(trace)])
+                        },
+                        Unwind::Throw(object, keyword) => {
+                            let message = self.uncaught_message(&object);
+                            let diagnostic = self.in_file(
+                                Diagnostic::runtime_error(&keyword, &message),
+                                &keyword
+                            );
+                            let trace = mem::take(&mut self.trace);
+                            Err(vec![diagnostic.with_trace(trace)])
+                        },
+                        Unwind::Compile(diagnostics) =>
+                            // An imported file that doesn't scan, parse, or
+                            // resolve is reported like the source being run.
+                            Err(diagnostics),
+                        Unwind::Exit(code) => {
+                            // Not an error. The script asked to stop.
+                            self.exit_code = Some(code);
+                            Ok(None)
+                        },
+                        Unwind::Return(..) =>
+                            // A panic here indicates an error in the resolver or interpreter.
+                            panic!("uncaught return"),
+                        Unwind::Break | Unwind::Continue =>
+                            // Likewise.
+                            panic!("break or continue outside of a loop"),
+                    };
+                }
+            };
         }
 
-        Ok(())
+        Ok(last)
     }
 
     pub fn evaluate(&mut self, expression: &Expr) -> Result<Object, Unwind> {
@@ -78,6 +270,123 @@ impl Interpreter {
         statement.accept(self)
     }
 
+    pub fn in_frame<F>(
+        &mut self,
+        function: &str, paren: &Token,
+        call: F
+    ) -> Result<(), Unwind>
+        where F: FnOnce(&mut Interpreter) -> Result<(), Unwind>
+    {
+        self.enter(format!("{}()", function), paren, call)
+    }
+
+    fn enter<F>(
+        &mut self,
+        function: String, paren: &Token,
+        call: F
+    ) -> Result<(), Unwind>
+        where F: FnOnce(&mut Interpreter) -> Result<(), Unwind>
+    {
+        // A frame is a function call or an imported file running.
+
+        if self.calls.len() >= self.max_depth {
+            return Err(Unwind::Error(Error::new(
+                paren, "Stack overflow.".to_string()
+            )));
+        }
+
+        self.calls.push(Call {
+            function,
+            line: paren.line,
+            file: paren.file,
+        });
+
+        let result = call(self);
+
+        // Take a snapshot of the call stack when a runtime error leaves the
+        // frame it happened in, before the stack unwinds any further.
+
+        let culprit = match result {
+            Err(Unwind::Error(ref error)) => Some(&error.token),
+            Err(Unwind::Throw(_, ref keyword)) => Some(keyword),
+            _ => None,
+        };
+
+        if let Some(culprit) = culprit {
+            if self.trace.is_empty() {
+                self.trace = self.snapshot(culprit.line, culprit.file);
+            }
+        }
+
+        self.calls.pop();
+
+        result
+    }
+
+    fn snapshot(&self, line: usize, file: usize) -> Vec<Frame> {
+        let mut trace = Vec::new();
+        let (mut line, mut file) = (line, file);
+
+        let frame = |function: &str, line: usize, file: usize| {
+            let frame = Frame::new(function, line);
+
+            match self.file_name(file) {
+                Some(name) => frame.in_file(name),
+                None => frame,
+            }
+        };
+
+        for call in self.calls.iter().rev() {
+            trace.push(frame(&call.function, line, file));
+            line = call.line;
+            file = call.file;
+        }
+
+        trace.push(frame("script", line, file));
+
+        trace
+    }
+
+    fn error_object(&self, error: &Error) -> Object {
+        // A runtime error becomes an Error instance when it's caught.
+
+        let mut instance = Instance::new(self.error_class.clone());
+
+        instance.set("message", &Object::String(error.message.clone()));
+        instance.set("line", &Object::Number(error.token.line as f64));
+
+        Object::Instance(instance)
+    }
+
+    fn uncaught_message(&self, object: &Object) -> String {
+        // A runtime error that was caught and thrown again reports its
+        // original message. Anything else is described.
+
+        if let Object::Instance(instance) = object {
+            if *instance.class() == self.error_class {
+                if let Some(Object::String(message)) = instance.get("message") {
+                    return message;
+                }
+            }
+        }
+
+        format!("Uncaught exception: {}.", object)
+    }
+
+    fn catch(
+        &mut self,
+        name: &Token, body: &[Stmt],
+        object: Object
+    ) -> Result<(), Unwind> {
+        // The exception was handled, so forget where it came from.
+        self.trace.clear();
+
+        let mut local = env::new_with_enclosing(&self.local);
+        env::define(&mut local, name.to_name().1, &object);
+
+        self.execute_block(body, local)
+    }
+
     pub fn execute_block(
         &mut self,
         statements: &[Stmt], new_local: env::Environment
@@ -101,12 +410,105 @@ impl Interpreter {
         Ok(())
     }
 
+    fn import(&mut self, keyword: &Token, path: &str) -> Result<Module, Unwind> {
+        // A path is relative to the file the import is written in, which
+        // isn't the file that's running if it's in a function called from
+        // somewhere else. At the prompt, it's relative to the working
+        // directory.
+
+        let origin = match keyword.file {
+            0 => self.files.first(),
+            file => Some(&self.sources[file - 1].path),
+        };
+
+        let relative = match origin.and_then(|file| file.parent()) {
+            Some(directory) => directory.join(path),
+            None => PathBuf::from(path),
+        };
+
+        let import_error = |reason: String| Unwind::Error(Error::new(
+            keyword, format!("Can't import '{}': {}.", path, reason)
+        ));
+
+        let file = fs::canonicalize(&relative).map_err(
+            |error| import_error(error.to_string())
+        )?;
+
+        if let Some(start) = self.files.iter().position(|open| *open == file) {
+            let cycle: Vec<_> = self.files[start..].iter().chain(iter::once(&file))
+                .map(|file| file.file_name().unwrap_or_default().to_string_lossy())
+                .collect();
+
+            return Err(import_error(format!("cycle {}", cycle.join(" -> "))));
+        }
+
+        // A file only runs the first time it's imported.
+
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+
+        let source = fs::read_to_string(&file).map_err(
+            |error| import_error(error.to_string())
+        )?;
+
+        // Name the file relative to the working directory if it's under it,
+        // like the path the source being run was given by.
+
+        let name = std::env::current_dir().ok()
+            .and_then(|directory| file.strip_prefix(directory).ok())
+            .unwrap_or(&file)
+            .display().to_string();
+
+        self.sources.push(Source {
+            name: name.clone(),
+            text: Rc::from(source.as_str()),
+            path: file.clone(),
+        });
+
+        let (statements, resolutions) = module::compile(
+            &source, self.sources.len(), &mut self.identifier_key, Parser::parse
+        ).map_err(|diagnostics| Unwind::Compile(
+            diagnostics.into_iter().map(
+                |diagnostic| diagnostic.in_file(&name)
+            ).collect()
+        ))?;
+
+        self.resolve(resolutions);
+
+        let global = env::new();
+        let importer = mem::replace(&mut self.local, env::copy(&global));
+        self.files.push(file.clone());
+
+        let result = self.enter(format!("module {}", path), keyword, |interpreter| {
+            for statement in &statements {
+                interpreter.execute(statement)?;
+            }
+
+            Ok(())
+        });
+
+        self.files.pop();
+        self.local = importer;
+        result?;
+
+        let module = Module::new(path, global);
+        self.modules.insert(file, module.clone());
+
+        Ok(module)
+    }
+
     fn look_up_variable(&self, token: &Token) -> Result<Object, Unwind> {
         let (identifier, name) = token.to_name();
 
+        // An unresolved variable is a global of the file the code came from,
+        // or a native.
+
         match self.resolutions.get(identifier) {
             Some(distance) => Ok(env::get_at(&self.local, *distance, name)),
-            None => env::get(&self.global, name).map_or_else(
+            None => env::get(&env::root(&self.local), name).or_else(
+                || env::get(&self.natives, name)
+            ).map_or_else(
                 || Err(Unwind::Error(Error::new(
                     token, format!("Undefined variable '{}'.", name)
                 ))),
@@ -130,7 +532,8 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
                 Ok(object)
             },
             None =>
-                if env::assign(&mut self.global, name, &object) {
+                if env::assign(&mut env::root(&self.local), name, &object)
+                    || env::assign(&mut self.natives, name, &object) {
                     Ok(object)
                 } else {
                     Err(Unwind::Error(Error::new(
@@ -219,6 +622,37 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
                             "Operands must be two numbers or two strings.".to_string(),
                         ))),
                 }
+            TT::Percent =>
+                // The remainder takes the sign of the divisor, so that
+                // a == b * (a ~/ b) + a % b.
+                match (left, right) {
+                    (Object::Number(left), Object::Number(right)) =>
+                        if right != 0 as f64 {
+                            let remainder = left % right;
+
+                            if remainder != 0 as f64 && (remainder < 0.0) != (right < 0.0) {
+                                // A remainder too small to matter next to the
+                                // divisor rounds to the divisor itself, which
+                                // leaves nothing over.
+                                let wrapped = remainder + right;
+                                Ok(Object::Number(
+                                    if wrapped == right { 0.0 } else { wrapped }
+                                ))
+                            } else {
+                                Ok(Object::Number(remainder))
+                            }
+                        } else {
+                            Err(Unwind::Error(Error::new(
+                                operator,
+                                "Division by zero.".to_string()
+                            )))
+                        }
+                    _ =>
+                        Err(Unwind::Error(Error::new(
+                            operator,
+                            "Operands must be numbers.".to_string()
+                        ))),
+                },
             TT::Slash =>
                 match (left, right) {
                     (Object::Number(left), Object::Number(right)) =>
@@ -246,6 +680,34 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
                             "Operands must be numbers.".to_string(),
                         ))),
                 },
+            TT::StarStar =>
+                match (left, right) {
+                    (Object::Number(left), Object::Number(right)) =>
+                        Ok(Object::Number(left.powf(right))),
+                    _ =>
+                        Err(Unwind::Error(Error::new(
+                            operator,
+                            "Operands must be numbers.".to_string(),
+                        ))),
+                },
+            TT::TildeSlash =>
+                // Integer division rounds toward negative infinity.
+                match (left, right) {
+                    (Object::Number(left), Object::Number(right)) =>
+                        if right != 0 as f64 {
+                            Ok(Object::Number((left / right).floor()))
+                        } else {
+                            Err(Unwind::Error(Error::new(
+                                operator,
+                                "Division by zero.".to_string()
+                            )))
+                        }
+                    _ =>
+                        Err(Unwind::Error(Error::new(
+                            operator,
+                            "Operands must be numbers.".to_string()
+                        ))),
+                },
 
             // A panic here indicates an error in the parser.
             _ => panic!("token is not a binary operator")
@@ -270,18 +732,20 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
                 panic!("more than 255 arguments");
             }
 
-            if arguments.len() as u8 != callable.arity() {
-                return Err(Unwind::Error(Error::new(
-                    paren,
-                    format!(
-                        "Expected {} arguments but got {}.",
-                        callable.arity(),
-                        arguments.len()
-                    )
-                )));
+            if let call::Arity::Fixed(arity) = callable.arity() {
+                if arguments.len() as u8 != arity {
+                    return Err(Unwind::Error(Error::new(
+                        paren,
+                        format!(
+                            "Expected {} arguments but got {}.",
+                            arity,
+                            arguments.len()
+                        )
+                    )));
+                }
             }
 
-            callable.call(self, objects)
+            callable.call(self, paren, objects)
         } else {
             Err(Unwind::Error(Error::new(
                 paren,
@@ -295,13 +759,32 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
         let name = token.to_name().1;
 
         match object {
-            Object::Instance(instance) =>
-                instance.get(name).map_or_else(
+            Object::Instance(instance) => match instance.get(name) {
+                // A getter runs as soon as it's accessed.
+                Some(Object::Callable(call::Callable::Function(method)))
+                    if method.is_getter() =>
+                        method.call(self, token, Vec::new()),
+                Some(object) => Ok(object),
+                None => Err(Unwind::Error(Error::new(
+                    token, format!("Undefined property '{}'.", name)
+                ))),
+            },
+            Object::Module(module) =>
+                module.get(name).map_or_else(
                     || Err(Unwind::Error(Error::new(
                         token, format!("Undefined property '{}'.", name)
                     ))),
                     Ok
                 ),
+            // A class only has its static methods. Anything else is an error,
+            // like it is for any other object that isn't an instance.
+            Object::Callable(call::Callable::Class(class)) =>
+                class.find_static(name).map_or_else(
+                    || Err(Unwind::Error(Error::new(
+                        token, "Only instances have properties.".to_string()
+                    ))),
+                    |method| Ok(Object::Callable(method.erase()))
+                ),
             _ => Err(Unwind::Error(Error::new(
                 token, "Only instances have properties.".to_string()
             )))
@@ -312,6 +795,64 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
         self.evaluate(expression)
     }
 
+    fn visit_index(
+        &mut self,
+        object: &Expr, bracket: &Token,
+        index: &Expr
+    ) -> Result<Object, Unwind> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        match object {
+            Object::List(list) => {
+                let index = to_index(bracket, &index, list.len())?;
+                Ok(list.get(index).unwrap_or(Object::Nil))
+            },
+            Object::Map(map) =>
+                map.get(&to_key(bracket, &index)?).map_or_else(
+                    || Err(Unwind::Error(Error::new(
+                        bracket, format!("Undefined key '{}'.", index)
+                    ))),
+                    Ok
+                ),
+            _ => Err(Unwind::Error(Error::new(
+                bracket, "Only lists and maps can be indexed.".to_string()
+            )))
+        }
+    }
+
+    fn visit_interpolation(&mut self, parts: &[Expr]) -> Result<Object, Unwind> {
+        // Every part is formatted like print would format it.
+
+        let mut string = String::new();
+
+        for part in parts {
+            string.push_str(&self.evaluate(part)?.to_string());
+        }
+
+        Ok(Object::String(string))
+    }
+
+    fn visit_lambda(&mut self, definition: &def::Function) -> Result<Object, Unwind> {
+        let function = call::Function::new(
+            definition.clone(),
+            env::copy(&self.local),
+            false
+        ).erase();
+
+        Ok(Object::Callable(function))
+    }
+
+    fn visit_list(&mut self, elements: &[Expr]) -> Result<Object, Unwind> {
+        let mut objects = Vec::new();
+
+        for element in elements {
+            objects.push(self.evaluate(element)?);
+        }
+
+        Ok(Object::List(List::new(objects)))
+    }
+
     fn visit_literal(&mut self, object: &Object) -> Result<Object, Unwind> {
         Ok(Object::clone(object))
     }
@@ -347,6 +888,19 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
         self.evaluate(right)
     }
 
+    fn visit_map(&mut self, entries: &[(Expr, Token, Expr)]) -> Result<Object, Unwind> {
+        let map = Map::new();
+
+        for (key, colon, value) in entries {
+            let key = self.evaluate(key)?;
+            let key = to_key(colon, &key)?;
+            let value = self.evaluate(value)?;
+            map.insert(key, &value);
+        }
+
+        Ok(Object::Map(map))
+    }
+
     fn visit_set(
         &mut self,
         object: &Expr, token: &Token,
@@ -368,6 +922,38 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
         }
     }
 
+    fn visit_set_index(
+        &mut self,
+        object: &Expr, bracket: &Token,
+        index: &Expr, value: &Expr
+    ) -> Result<Object, Unwind> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+
+        match object {
+            Object::List(list) => {
+                let index = to_index(bracket, &index, list.len())?;
+                let value = self.evaluate(value)?;
+
+                // The value may have shrunk the list out from under the index.
+                if list.set(index, &value) { Ok(value) } else {
+                    Err(Unwind::Error(Error::new(
+                        bracket, "List index out of range.".to_string()
+                    )))
+                }
+            },
+            Object::Map(map) => {
+                let key = to_key(bracket, &index)?;
+                let value = self.evaluate(value)?;
+                map.insert(key, &value);
+                Ok(value)
+            },
+            _ => Err(Unwind::Error(Error::new(
+                bracket, "Only lists and maps can be indexed.".to_string()
+            )))
+        }
+    }
+
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> Result<Object, Unwind> {
         if let Some(&distance) = self.resolutions.get(keyword.to_name().0) {
             let parent = env::get_at(&self.local, distance, "super");
@@ -383,12 +969,15 @@ impl expr::Visitor<Result<Object, Unwind>> for Interpreter {
                 panic!("failed to narrow this instance")
             };
 
-            return parent.find_method(method.to_name().1).map_or_else(
-                || Err(Unwind::Error(Error::new(method,
+            return match parent.find_method(method.to_name().1) {
+                Some(function) if function.is_getter() =>
+                    function.bind(&this).call(self, method, Vec::new()),
+                Some(function) =>
+                    Ok(Object::Callable(function.bind(&this).erase())),
+                None => Err(Unwind::Error(Error::new(method,
                     format!("Undefined property '{}'.", method.to_name().1)
                 ))),
-                |method| Ok(Object::Callable(method.bind(&this).erase()))
-            )
+            }
         }
 
         // A panic here indicates an error in the resolver.
@@ -433,8 +1022,12 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         )
     }
 
+    fn visit_break(&mut self, _: &Token) -> Result<(), Unwind> {
+        Err(Unwind::Break)
+    }
+
     fn visit_class(&mut self, definition: &def::Class) -> Result<(), Unwind> {
-        let def::Class(name, parent_name, function_definitions) = definition;
+        let def::Class(name, parent_name, function_definitions, static_definitions) = definition;
         let class_name = name.to_name().1;
 
         let parent = if let Some(parent_name) = parent_name {
@@ -457,7 +1050,7 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         let above_super = env::copy(&self.local);
 
         if let Some(ref parent) = parent {
-            let mut with_super = env::new();
+            let mut with_super = env::new_with_enclosing(&self.local);
             let object = Object::Callable(parent.as_ref().clone().erase());
             env::define(&mut with_super, "super", &object);
             self.local = with_super;
@@ -481,10 +1074,28 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
 
         if parent.is_some() { self.local = above_super; }
 
+        // Static methods don't have 'this' or 'super'.
+
+        let mut statics = FxHashMap::default();
+
+        for function_definition in static_definitions {
+            let def::Function(function_name, ..) = function_definition;
+
+            statics.insert(
+                function_name.to_name().1.to_string(),
+                call::Function::new(
+                    function_definition.clone(),
+                    env::copy(&self.local),
+                    false
+                )
+            );
+        }
+
         let class = call::Class::new(
             name.clone(),
             parent,
-            Rc::new(methods)
+            Rc::new(methods),
+            Rc::new(statics)
         ).erase();
 
         env::define(&mut self.local, class_name, &Object::Callable(class));
@@ -492,6 +1103,10 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         Ok(())
     }
 
+    fn visit_continue(&mut self, _: &Token) -> Result<(), Unwind> {
+        Err(Unwind::Continue)
+    }
+
     fn visit_expression(&mut self, expression: &Expr) -> Result<(), Unwind> {
         self.evaluate(expression)?;
         Ok(())
@@ -530,19 +1145,31 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         Ok(())
     }
 
+    fn visit_import(
+        &mut self,
+        keyword: &Token, path: &str, name: &Token
+    ) -> Result<(), Unwind> {
+        let module = Object::Module(self.import(keyword, path)?);
+        env::define(&mut self.local, name.to_name().1, &module);
+        Ok(())
+    }
+
     fn visit_print(&mut self, object: &Expr) -> Result<(), Unwind> {
         let object: Object = self.evaluate(object)?;
-        println!("{}", object);
+
+        // Like println!, but a failure to write isn't worth killing the
+        // program over.
+        let _ = writeln!(self.stdout, "{}", object);
+
         Ok(())
     }
 
     fn visit_return(
         &mut self,
-        keyword: &Token,
+        _: &Token,
         object: &Option<Expr>
     ) -> Result<(), Unwind> {
         Err(Unwind::Return(
-            keyword.clone(),
             if let Some(object) = object {
                 self.evaluate(object)?
             } else {
@@ -551,6 +1178,51 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         ))
     }
 
+    fn visit_throw(&mut self, keyword: &Token, object: &Expr) -> Result<(), Unwind> {
+        let object = self.evaluate(object)?;
+        Err(Unwind::Throw(Box::new(object), Token::clone(keyword)))
+    }
+
+    fn visit_try(
+        &mut self, body: &[Stmt],
+        catch: &Option<(Token, Vec<Stmt>)>, finally: &Option<Vec<Stmt>>
+    ) -> Result<(), Unwind> {
+        let result = self.execute_block(
+            body, env::new_with_enclosing(&self.local)
+        );
+
+        let result = match (result, catch) {
+            (Err(Unwind::Error(error)), Some((name, body))) => {
+                let object = self.error_object(&error);
+                self.catch(name, body, object)
+            },
+            (Err(Unwind::Throw(object, _)), Some((name, body))) =>
+                self.catch(name, body, *object),
+            (result, _) => result,
+        };
+
+        // Exiting skips the finally block. Anything else runs it, and an
+        // unwind from the finally block replaces whatever was pending.
+
+        if let Err(Unwind::Exit(_)) = result {
+            return result;
+        }
+
+        // The trace of a pending error only survives the finally block if
+        // the error does. Otherwise it would be blamed for the next one.
+
+        if let Some(body) = finally {
+            let trace = mem::take(&mut self.trace);
+            self.execute_block(body, env::new_with_enclosing(&self.local))?;
+
+            if let Err(Unwind::Error(_)) | Err(Unwind::Throw(..)) = result {
+                self.trace = trace;
+            }
+        }
+
+        result
+    }
+
     fn visit_var(&mut self, name: &Token, object: &Option<Expr>) -> Result<(), Unwind> {
         let name = name.to_name().1;
 
@@ -564,15 +1236,64 @@ impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
         Ok(())
     }
 
-    fn visit_while(&mut self, condition: &Expr, body: &Stmt) -> Result<(), Unwind> {
+    fn visit_while(
+        &mut self, condition: &Expr,
+        body: &Stmt, increment: &Option<Expr>
+    ) -> Result<(), Unwind> {
         while is_truthy(&self.evaluate(condition)?) {
-            self.execute(body)?;
+            match self.execute(body) {
+                Err(Unwind::Break) => break,
+                Err(Unwind::Continue) | Ok(()) => (),
+                Err(unwind) => return Err(unwind),
+            }
+
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
         }
 
         Ok(())
     }
 }
 
+fn error_class() -> call::Class {
+    // The class of caught runtime errors. It isn't a global, so its name
+    // doesn't need an identifier key from the scanner.
+
+    let name = Token::new(
+        TT::Identifier(usize::MAX, "Error".to_string()), "Error".to_string(),
+        0, 0, Span::default()
+    );
+
+    let methods = Rc::new(FxHashMap::default());
+    call::Class::new(Rc::new(name), None, Rc::clone(&methods), methods)
+}
+
+fn to_index(bracket: &Token, index: &Object, len: usize) -> Result<usize, Unwind> {
+    // Lists are indexed by whole numbers from zero.
+
+    let index = match index {
+        Object::Number(number) if number.fract() == 0.0 => *number,
+        _ => return Err(Unwind::Error(Error::new(
+            bracket, "List index must be an integer.".to_string()
+        )))
+    };
+
+    if index < 0.0 || index >= len as f64 {
+        return Err(Unwind::Error(Error::new(
+            bracket, "List index out of range.".to_string()
+        )));
+    }
+
+    Ok(index as usize)
+}
+
+fn to_key(token: &Token, key: &Object) -> Result<Key, Unwind> {
+    Key::from_object(key).ok_or_else(
+        || Unwind::Error(Error::new(token, map::KEY_ERROR.to_string()))
+    )
+}
+
 #[allow(clippy::match_like_matches_macro)]
 fn is_truthy(operand: &Object) -> bool {
     // We're following Ruby because Ruby is pretty. 'false' and 'nil' are
@@ -1,15 +1,28 @@
 pub mod lox;
 
+pub use callable::Arity;
+pub use error::{Diagnostic, Diagnostics, Frame, Label, Location, Phase, Severity, Style};
+pub use interpreter::{MAX_DEPTH, STACK_SIZE};
+pub use object::Object;
+pub use prelude::Library;
+pub use session::Session;
+pub use token::Span;
+
 mod callable;
 mod environment;
 mod expression;
 mod error;
 mod instance;
 mod interpreter;
+mod list;
+mod map;
+mod module;
 mod object;
 mod parser;
+mod prelude;
 mod resolver;
 mod token;
 mod token_type;
 mod scanner;
+mod session;
 mod statement;
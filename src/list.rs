@@ -0,0 +1,105 @@
+use std::cmp;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::object::Object;
+
+// Like an instance, a list is shared by every variable that refers to it, so
+// pushing onto one alias is visible through the others.
+
+#[derive(Debug, Clone)]
+pub struct List {
+    elements: Rc<RefCell<Vec<Object>>>
+}
+
+impl List {
+    pub fn new(elements: Vec<Object>) -> List {
+        List {
+            elements: Rc::new(RefCell::new(elements))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.borrow().len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Object> {
+        self.elements.borrow().get(index).cloned()
+    }
+
+    pub fn set(&self, index: usize, object: &Object) -> bool {
+        match self.elements.borrow_mut().get_mut(index) {
+            Some(element) => { *element = object.clone(); true },
+            None => false,
+        }
+    }
+
+    pub fn push(&self, object: &Object) {
+        self.elements.borrow_mut().push(object.clone());
+    }
+
+    pub fn pop(&self) -> Option<Object> {
+        self.elements.borrow_mut().pop()
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Hold the list mutably while it's printed. A list that contains
+        // itself can't borrow itself again, so it prints as an ellipsis
+        // instead of recursing forever.
+
+        let elements = match self.elements.try_borrow_mut() {
+            Ok(elements) => elements,
+            Err(_) => return write!(f, "[...]"),
+        };
+
+        write!(f, "[")?;
+
+        for (i, element) in elements.iter().enumerate() {
+            if i > 0 { write!(f, ", ")?; }
+            write!(f, "{}", element)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl cmp::PartialEq for List {
+    fn eq(&self, other: &List) -> bool {
+        // Two lists are equal if they're the same list.
+        Rc::ptr_eq(&self.elements, &other.elements)
+    }
+}
+
+pub fn len(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::List(list) =>
+            Ok(Object::Number(list.len() as f64)),
+        Object::Map(map) =>
+            Ok(Object::Number(map.len() as f64)),
+        Object::String(string) =>
+            Ok(Object::Number(string.chars().count() as f64)),
+        _ => Err("Can only take the length of a list, map, or string.".to_string()),
+    }
+}
+
+pub fn push(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::List(list) => {
+            list.push(&arguments[1]);
+            Ok(Object::Nil)
+        },
+        _ => Err("Can only push onto a list.".to_string()),
+    }
+}
+
+pub fn pop(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::List(list) => list.pop().ok_or_else(
+            || "Can't pop from an empty list.".to_string()
+        ),
+        _ => Err("Can only pop from a list.".to_string()),
+    }
+}
@@ -3,101 +3,160 @@ use std::error;
 use std::fs;
 use std::io::{self, Write};
 use std::process;
+use std::thread;
 
-use crate::error::LoxError;
-use crate::interpreter::Interpreter;
-use crate::parser::Parser;
-use crate::resolver::Resolver;
-use crate::scanner::Scanner;
+use crate::error::{Diagnostics, Style};
+use crate::interpreter::STACK_SIZE;
+use crate::prelude::Library;
+use crate::session::Session;
 
 // Exit codes from FreeBSD's 'sysexits.h' header: https://bit.ly/36JtSK0.
 
 pub fn interact() {
-    process::exit(match lox() {
+    // The main thread's stack is too small for deep recursion. See
+    // interpreter.rs. A panic has already said what went wrong.
+
+    let lox = thread::Builder::new().stack_size(STACK_SIZE).spawn(lox);
+
+    let status = match fatal(lox, 71) {
+        Ok(lox) => lox.join().unwrap_or(Err(70)),
+        Err(exit_code) => Err(exit_code),
+    };
+
+    process::exit(match status {
         Err(exit_code) => exit_code,
         Ok(())         => 0
     });
 }
 
 fn lox() -> Result<(), i32> {
-    let args: Vec<String> = env::args().skip(1).collect();
-
-    match args.len() {
-        0 => run_prompt(),
-        1 => run_file(&args[0]),
-        _ => {
-            println!("usage: rlox [script]");
-            Err(64)
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut session = Session::new();
+    let mut pretty = false;
+    let mut json = false;
+    let mut sandbox = false;
+
+    // Options come before the script. Anything after the script belongs to
+    // the script.
+
+    while args.first().is_some_and(|arg| arg.starts_with("--")) {
+        match args.remove(0).as_str() {
+            "--pretty"  => pretty = true,
+            "--json"    => json = true,
+            "--sandbox" => sandbox = true,
+            _ => return usage(),
         }
     }
+
+    // The two styles of reporting are exclusive.
+
+    match (pretty, json) {
+        (true, true) => return usage(),
+        (true, false) => session.set_style(Style::Rich),
+        _ => (),
+    }
+
+    // A sandboxed script can't touch files, standard input, or the process.
+    if !sandbox { session.load(Library::Io); }
+
+    // With --json the session keeps quiet and we report its diagnostics
+    // ourselves, one JSON object per line.
+
+    if json { session.set_stderr(io::sink()); }
+
+    if args.is_empty() {
+        session.set_args(Vec::new());
+        run_prompt(session, json)
+    } else {
+        let path = args.remove(0);
+        session.set_args(args);
+        run_file(session, &path, json)
+    }
+}
+
+fn usage() -> Result<(), i32> {
+    println!("usage: rlox [--pretty | --json] [--sandbox] [script [args...]]");
+    Err(64)
 }
 
-fn run_file(path: &str) -> Result<(), i32> {
+fn run_file(mut session: Session, path: &str, json: bool) -> Result<(), i32> {
     let contents = fatal(fs::read_to_string(path), 66)?;
-    let status = run(&contents);
+    session.set_path(path);
+    let status = session.run(&contents);
 
     match status {
-        Err(LoxError::Scan)      => Err(65),
-        Err(LoxError::Parse)     => Err(65),
-        Err(LoxError::Resolve)   => Err(65),
-        Err(LoxError::Interpret) => Err(70),
-        Ok(())                   => Ok(()),
+        Err(diagnostics) => {
+            if json { report_json(&diagnostics, path); }
+            Err(diagnostics[0].phase.exit_code())
+        },
+        Ok(_) => exit(session.exit_code()),
     }
 }
 
-fn run_prompt() -> Result<(), i32> {
+fn report_json(diagnostics: &Diagnostics, file: &str) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.to_json(file));
+    }
+}
+
+fn run_prompt(mut session: Session, json: bool) -> Result<(), i32> {
+    let mut source = String::new();
+
     loop {
         let stdin = io::stdin();
         let mut stdout = io::stdout();
 
-        print!("> ");
+        // Continue the prompt while a declaration spans multiple lines.
+        print!("{}", if source.is_empty() { "> " } else { "... " });
         fatal(stdout.flush(), 74)?;
 
         let mut line = String::new();
-        fatal(stdin.read_line(&mut line), 74)?;
-        let line = line.trim();
+        let read = fatal(stdin.read_line(&mut line), 74)?;
 
-        if line.is_empty() {
+        if source.is_empty() && line.trim().is_empty() {
             return Ok(());
         }
 
-        // Absorb any error from the scanner, parser, or interpreter.
-        let _: Result<(), LoxError> = run(line);
-    }
-}
+        source.push_str(&line);
 
-fn run(source: &str) -> Result<(), LoxError> {
-    let mut scanner = Scanner::new(source);
-    scanner.scan_tokens();
-    let tokens = scanner.consume()?;
+        // An empty line or the end of input gives up on an unfinished
+        // declaration and reports its error.
+        let give_up = read == 0 || line.trim().is_empty();
 
-    // for token in tokens.iter() {
-    //     println!("{:?}", token);
-    // }
+        if !give_up && session.is_unfinished(&source) {
+            continue;
+        }
 
-    let mut parser = Parser::new(tokens);
-    parser.parse();
-    let statements = parser.consume()?;
+        // Absorb any error from the scanner, parser, or interpreter. Echo the
+        // value of a trailing expression statement.
+        match session.run(&source) {
+            Ok(Some(object)) => println!("{}", object),
+            Err(diagnostics) if json => report_json(&diagnostics, "<prompt>"),
+            _ => (),
+        }
 
-    // for statement in &statements {
-    //     println!("{:#?}", statement);
-    // }
+        if session.exit_code().is_some() {
+            return exit(session.exit_code());
+        }
 
-    let mut resolver = Resolver::new();
-    resolver.resolve_statements(&statements);
-    let resolutions = resolver.consume()?;
+        source.clear();
+    }
+}
 
-    let mut interpreter = Interpreter::new(resolutions);
-    interpreter.interpret(statements)?;
+fn exit(exit_code: Option<i32>) -> Result<(), i32> {
+    // The script may have called 'exit' with a status for the shell.
 
-    Ok(())
+    match exit_code {
+        None | Some(0) => Ok(()),
+        Some(exit_code) => Err(exit_code),
+    }
 }
 
 pub fn fatal<T, E: error::Error>(result: Result<T, E>, exit_code: i32) -> Result<T, i32> {
     match result {
         Ok(value) => Ok(value),
         Err(error) => {
-            eprintln!("fatal: {}", error.to_string());
+            eprintln!("fatal: {}", error);
             Err(exit_code)
         }
     }
@@ -0,0 +1,191 @@
+use std::cmp;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+
+use crate::list::List;
+use crate::object::Object;
+
+// Only values that are compared by value can be keys: nil, booleans, numbers,
+// and strings. Numbers are keyed by value, so 0 and -0 are the same key, and
+// NaN isn't a key at all because it isn't equal to itself. Instances, lists,
+// maps, and callables are rejected.
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Nil,
+    Boolean(bool),
+    Number(u64),
+    String(String),
+}
+
+pub const KEY_ERROR: &str = "Map keys must be nil, booleans, numbers, or strings.";
+
+impl Key {
+    pub fn from_object(object: &Object) -> Option<Key> {
+        match object {
+            Object::Nil => Some(Key::Nil),
+            Object::Boolean(bool) => Some(Key::Boolean(*bool)),
+            Object::Number(number) if number.is_nan() => None,
+            Object::Number(number) =>
+                Some(Key::Number((number + 0.0).to_bits())),
+            Object::String(string) => Some(Key::String(string.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            Key::Nil => Object::Nil,
+            Key::Boolean(bool) => Object::Boolean(*bool),
+            Key::Number(bits) => Object::Number(f64::from_bits(*bits)),
+            Key::String(string) => Object::String(string.clone()),
+        }
+    }
+}
+
+// Entries are kept in insertion order so that printing a map or asking for its
+// keys is deterministic.
+
+#[derive(Debug, Default)]
+struct Entries {
+    order: Vec<Key>,
+    values: FxHashMap<Key, Object>,
+}
+
+// Like a list, a map is shared by every variable that refers to it.
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    entries: Rc<RefCell<Entries>>
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map {
+            entries: Rc::new(RefCell::new(Entries::default()))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().order.len()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Object> {
+        self.entries.borrow().values.get(key).cloned()
+    }
+
+    pub fn has(&self, key: &Key) -> bool {
+        self.entries.borrow().values.contains_key(key)
+    }
+
+    pub fn insert(&self, key: Key, object: &Object) {
+        let mut entries = self.entries.borrow_mut();
+
+        if entries.values.insert(key.clone(), object.clone()).is_none() {
+            entries.order.push(key);
+        }
+    }
+
+    pub fn remove(&self, key: &Key) -> Option<Object> {
+        let mut entries = self.entries.borrow_mut();
+        let removed = entries.values.remove(key);
+
+        if removed.is_some() {
+            entries.order.retain(|k| k != key);
+        }
+
+        removed
+    }
+
+    pub fn keys(&self) -> Vec<Object> {
+        self.entries.borrow().order.iter().map(Key::to_object).collect()
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A map that contains itself prints as an ellipsis. See list.rs.
+
+        let entries = match self.entries.try_borrow_mut() {
+            Ok(entries) => entries,
+            Err(_) => return write!(f, "[...]"),
+        };
+
+        // The empty map prints like its literal, [:].
+
+        if entries.order.is_empty() {
+            return write!(f, "[:]");
+        }
+
+        write!(f, "[")?;
+
+        for (i, key) in entries.order.iter().enumerate() {
+            if i > 0 { write!(f, ", ")?; }
+            write!(f, "{}: {}", key.to_object(), entries.values[key])?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl cmp::PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        // Two maps are equal if they're the same map.
+        Rc::ptr_eq(&self.entries, &other.entries)
+    }
+}
+
+fn key(object: &Object) -> Result<Key, String> {
+    Key::from_object(object).ok_or_else(|| KEY_ERROR.to_string())
+}
+
+pub fn keys(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::Map(map) => Ok(Object::List(List::new(map.keys()))),
+        _ => Err("Can only take the keys of a map.".to_string()),
+    }
+}
+
+pub fn has(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::Map(map) =>
+            Ok(Object::Boolean(map.has(&key(&arguments[1])?))),
+        _ => Err("Can only look for a key in a map.".to_string()),
+    }
+}
+
+pub fn remove(arguments: Vec<Object>) -> Result<Object, String> {
+    match &arguments[0] {
+        Object::Map(map) =>
+            Ok(map.remove(&key(&arguments[1])?).unwrap_or(Object::Nil)),
+        _ => Err("Can only remove a key from a map.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_keys() {
+        let key = |object| Key::from_object(&object);
+
+        assert_eq!(key(Object::Number(0.0)), key(Object::Number(-0.0)));
+        assert_ne!(key(Object::Number(1.0)), key(Object::String("1".to_string())));
+        assert_eq!(key(Object::Number(f64::NAN)), None);
+        assert_eq!(key(Object::List(List::new(Vec::new()))), None);
+
+        let map = Map::new();
+        map.insert(Key::String("b".to_string()), &Object::Nil);
+        map.insert(Key::Number(1.5f64.to_bits()), &Object::Nil);
+        map.insert(Key::String("b".to_string()), &Object::Boolean(true));
+
+        assert_eq!(map.keys(), vec![
+            Object::String("b".to_string()),
+            Object::Number(1.5),
+        ]);
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp;
+use std::fmt;
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+
+use crate::environment::{self as env, Environment};
+use crate::error::Diagnostics;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::statement::Stmt;
+
+// An imported file. Its top-level declarations live in their own global
+// environment, and the importer reaches them as properties of the module.
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    name: Rc<str>,
+    global: Environment,
+}
+
+impl Module {
+    pub fn new(name: &str, global: Environment) -> Module {
+        Module { name: Rc::from(name), global }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        // A module's global environment doesn't enclose anything, so this
+        // never finds a native.
+        env::get(&self.global, name)
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+impl cmp::PartialEq for Module {
+    fn eq(&self, other: &Module) -> bool {
+        // A file is only run once, so two modules are equal if they're the
+        // same import.
+        Rc::ptr_eq(&self.global, &other.global)
+    }
+}
+
+pub type Resolutions = FxHashMap<usize, usize>;
+
+pub fn compile<P>(
+    source: &str, file: usize,
+    identifier_key: &mut usize, parse: P
+) -> Result<(Vec<Stmt>, Resolutions), Diagnostics>
+    where P: FnOnce(&mut Parser)
+{
+    // Scan, parse, and resolve a file's source. Its identifier keys pick up
+    // where the last file's left off so that their resolutions never collide.
+    // Its tokens remember which file they're from, for runtime errors.
+
+    let mut scanner = Scanner::new(source, *identifier_key);
+    scanner.scan_tokens();
+    *identifier_key = scanner.identifier_key();
+    let (mut tokens, mut diagnostics) = scanner.consume();
+
+    for token in &mut tokens {
+        token.file = file;
+    }
+
+    let mut parser = Parser::new(tokens);
+    parse(&mut parser);
+
+    // Report the parser's errors along with the scanner's, but don't go any
+    // further if either stumbled.
+
+    let statements = match parser.consume() {
+        Ok(statements) if diagnostics.is_empty() => statements,
+        Ok(_) => return Err(diagnostics),
+        Err(more) => {
+            diagnostics.extend(more);
+            return Err(diagnostics);
+        }
+    };
+
+    let mut resolver = Resolver::new();
+    resolver.resolve_statements(&statements);
+    let resolutions = resolver.consume()?;
+
+    Ok((statements, resolutions))
+}
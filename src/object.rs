@@ -2,12 +2,18 @@ use std::fmt;
 
 use crate::callable::Callable;
 use crate::instance::Instance;
+use crate::list::List;
+use crate::map::Map;
+use crate::module::Module;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Object {
     Boolean(bool),
     Callable(Callable),
     Instance(Instance),
+    List(List),
+    Map(Map),
+    Module(Module),
     Nil,
     Number(f64),
     String(String),
@@ -19,6 +25,9 @@ impl fmt::Display for Object {
             Object::Boolean(bool)      => write!(f, "{}", bool),
             Object::Instance(instance) => write!(f, "{}", instance),
             Object::Callable(callable) => write!(f, "{}", callable),
+            Object::List(list)         => write!(f, "{}", list),
+            Object::Map(map)           => write!(f, "{}", map),
+            Object::Module(module)     => write!(f, "{}", module),
             Object::Nil                => write!(f, "nil"),
             Object::Number(float)      => write!(f, "{}", float),
             Object::String(string)     => write!(f, "{}", string),
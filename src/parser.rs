@@ -1,11 +1,13 @@
+use std::ffi::OsStr;
+use std::path::Path;
 use std::rc::Rc;
 
 use crate::callable::definitions as def;
-use crate::error;
+use crate::error::{Diagnostic, Diagnostics, Location};
 use crate::object::Object;
 use crate::expression::Expr;
 use crate::statement::Stmt;
-use crate::token::Token;
+use crate::token::{Span, Token};
 use crate::token_type::TokenType as TT;
 
 struct Error {
@@ -19,6 +21,15 @@ impl Error {
     }
 }
 
+fn is_name(name: &str) -> bool {
+    // Whether a module's file name could be written as an identifier.
+
+    let mut characters = name.chars();
+
+    characters.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|rest| rest.is_ascii_alphanumeric() || rest == '_')
+}
+
 fn to_object(token: Token) -> Object {
     match token.token_type {
         TT::False          => Object::Boolean(false),
@@ -30,20 +41,24 @@ fn to_object(token: Token) -> Object {
     }
 }
 
-type Tokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
+// The tokens are stored in reverse, so advancing pops the next one off the end
+// and looking two tokens ahead is cheap. The span of the last token popped is
+// where the statement being parsed ends so far.
 
 pub struct Parser {
-    tokens: Tokens,
+    tokens: Vec<Token>,
+    previous: Span,
     statements: Vec<Stmt>,
-    stumbled: bool,
+    diagnostics: Diagnostics,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
         Parser {
-            tokens: tokens.into_iter().peekable(),
+            tokens: tokens.into_iter().rev().collect(),
+            previous: Span::default(),
             statements: Vec::new(),
-            stumbled: false,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -55,21 +70,59 @@ impl Parser {
         }
     }
 
-    pub fn consume(self) -> Result<Vec<Stmt>, error::LoxError> {
-        if !self.stumbled {
+    pub fn parse_expression(&mut self) {
+        // Parse a lone expression, without a trailing semicolon, as though it
+        // were an expression statement.
+
+        let result = self.expression().and_then(|expression| {
+            if self.is_at_end() {
+                Ok(expression)
+            } else {
+                Err(Error::new(
+                    Token::clone(self.peek()),
+                    "Expect end of expression.".to_string()
+                ))
+            }
+        });
+
+        match result {
+            Ok(expression) => self.statements.push(
+                Stmt::Expression(expression.span(), expression)
+            ),
+            Err(panic) => self.stumble(panic.token, &panic.message),
+        }
+    }
+
+    pub fn is_unfinished(&self) -> bool {
+        // Running out of tokens in the middle of a declaration means the
+        // programmer may not be done typing it.
+
+        self.diagnostics.iter().any(
+            |diagnostic| diagnostic.location == Location::End
+        )
+    }
+
+    pub fn consume(self) -> Result<Vec<Stmt>, Diagnostics> {
+        if self.diagnostics.is_empty() {
             Ok(self.statements)
         } else {
-            Err(error::LoxError::Parse)
+            Err(self.diagnostics)
         }
     }
 
     fn declaration(&mut self) -> Option<Stmt> {
-        let result = if self.advance_if(&[TT::Class]).is_some() {
-            self.class_declaration()
-        } else if self.advance_if(&[TT::Fun]).is_some() {
-            self.function("function").map(Stmt::Function)
-        } else if self.advance_if(&[TT::Var]).is_some() {
-            self.variable_declaration()
+        let result = if let Some(keyword) = self.advance_if(&[TT::Class]) {
+            self.class_declaration(keyword)
+        } else if self.check(&TT::Fun) && !self.check_next(&TT::LeftParen) {
+            // An anonymous function is an expression statement.
+            let keyword = self.advance();
+            self.function("function").map(
+                |definition| Stmt::Function(self.since(&keyword), definition)
+            )
+        } else if let Some(keyword) = self.advance_if(&[TT::Import]) {
+            self.import_declaration(keyword)
+        } else if let Some(keyword) = self.advance_if(&[TT::Var]) {
+            self.variable_declaration(keyword)
         } else {
             self.statement()
         };
@@ -77,15 +130,14 @@ impl Parser {
         match result {
             Ok(declaration) => Some(declaration),
             Err(panic) => {
-                self.stumbled = true;
-                error::parse_error(&panic.token, &panic.message);
+                self.stumble(panic.token, &panic.message);
                 self.synchronize();
                 None
             }
         }
     }
 
-    fn class_declaration(&mut self) -> Result<Stmt, Error> {
+    fn class_declaration(&mut self, keyword: Token) -> Result<Stmt, Error> {
         let name = self.expect_identifier("Expect class name.".to_string())?;
        
         let parent = if self.advance_if(&[TT::Less]).is_some() {
@@ -94,14 +146,28 @@ impl Parser {
 
         self.expect(TT::LeftBrace, "Expect '{' before class body.".to_string())?;
 
+        // A method that starts with 'class' is a static method.
+
         let mut methods = Vec::new();
+        let mut statics = Vec::new();
+
         while !self.check(&TT::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            if self.advance_if(&[TT::Class]).is_some() {
+                statics.push(self.function("method")?);
+            } else {
+                methods.push(self.method()?);
+            }
         }
 
         self.expect(TT::RightBrace, "Expect '}' after class body.".to_string())?;
 
-        Ok(Stmt::Class(def::Class(Rc::new(name), parent.map(Rc::new), methods)))
+        let name = Rc::new(name);
+        let parent = parent.map(Rc::new);
+
+        Ok(Stmt::Class(
+            self.since(&keyword),
+            def::Class(name, parent, methods, statics)
+        ))
     }
 
     fn function(&mut self, kind: &str) -> Result<def::Function, Error> {
@@ -114,6 +180,58 @@ impl Parser {
             format!("Expect '(' after {} name.", kind)
         )?;
 
+        self.function_rest(name, kind)
+    }
+
+    fn method(&mut self) -> Result<def::Function, Error> {
+        let name = self.expect_identifier("Expect method name.".to_string())?;
+
+        // A method without a parameter list is a getter.
+
+        if self.advance_if(&[TT::LeftBrace]).is_some() {
+            let body = self.block()?;
+
+            return Ok(def::Function(
+                Rc::new(name),
+                Rc::new(Vec::new()),
+                Rc::new(body),
+                true
+            ));
+        }
+
+        self.expect(
+            TT::LeftParen,
+            "Expect '(' after method name.".to_string()
+        )?;
+
+        self.function_rest(name, "method")
+    }
+
+    fn lambda(&mut self, keyword: Token) -> Result<Expr, Error> {
+        self.expect(TT::LeftParen, "Expect '(' after 'fun'.".to_string())?;
+
+        // An anonymous function is named after its keyword so that it has a
+        // line to blame. The name is never declared, so its identifier key
+        // doesn't matter.
+
+        let start = keyword.span;
+
+        let name = Token {
+            file: keyword.file,
+            ..Token::new(
+                TT::Identifier(usize::MAX, "anonymous".to_string()),
+                keyword.lexeme, keyword.line, keyword.column, keyword.span
+            )
+        };
+
+        let definition = self.function_rest(name, "function")?;
+        Ok(Expr::Lambda(start.to(self.previous), definition))
+    }
+
+    fn function_rest(
+        &mut self,
+        name: Token, kind: &str
+    ) -> Result<def::Function, Error> {
         let parameters = self.parameters()?;
 
         self.expect(
@@ -127,6 +245,7 @@ impl Parser {
            Rc::new(name),
            Rc::new(parameters),
            Rc::new(body),
+           false
         ))
     }
 
@@ -140,11 +259,8 @@ impl Parser {
             while self.advance_if(&[TT::Comma]).is_some() {
                 if !too_many && parameters.len() >= 255 {
                     too_many = true;
-                    error::parse_error(
-                        self.peek(),
-                        "Can't have more than 255 parameters."
-                    );
-                    self.stumbled = true;
+                    let next = Token::clone(self.peek());
+                    self.stumble(next, "Can't have more than 255 parameters.");
                 }
                 parameters.push(self.parameter()?);
             }
@@ -166,7 +282,61 @@ impl Parser {
         Ok(parameter)
     }
 
-    fn variable_declaration(&mut self) -> Result<Stmt, Error> {
+    fn import_declaration(&mut self, keyword: Token) -> Result<Stmt, Error> {
+        // A module is a path in a string, or a bare name for a file next to
+        // the importer. It's bound to its file's name unless it's given
+        // another with 'as'.
+
+        let (path, default_name) = match self.peek().token_type {
+            TT::String(ref file) => {
+                let file = file.clone();
+                let path = self.advance();
+                let stem = Path::new(&file).file_stem().and_then(OsStr::to_str);
+                let name = stem.filter(|stem| is_name(stem)).map(
+                    |stem| Token {
+                        file: path.file,
+                        ..Token::new(
+                            TT::Identifier(usize::MAX, stem.to_string()),
+                            path.lexeme, path.line, path.column, path.span
+                        )
+                    }
+                );
+                (file, name)
+            },
+            TT::Identifier(_, ref name) => {
+                let file = format!("{}.lox", name);
+                (file, Some(self.advance()))
+            },
+            _ => return Err(Error::new(
+                Token::clone(self.peek()),
+                "Expect module path after 'import'.".to_string()
+            )),
+        };
+
+        let name = if self.check_as() {
+            self.advance();
+            self.expect_identifier("Expect module name after 'as'.".to_string())?
+        } else {
+            match default_name {
+                Some(name) => name,
+                None => return Err(Error::new(
+                    Token::clone(self.peek()),
+                    "Expect 'as' after a module path that isn't a name.".to_string()
+                )),
+            }
+        };
+
+        self.expect(TT::Semicolon, "Expect ';' after import.".to_string())?;
+
+        Ok(Stmt::Import(self.since(&keyword), keyword, path, name))
+    }
+
+    fn check_as(&mut self) -> bool {
+        // 'as' isn't reserved, so it can still name a variable.
+        matches!(self.peek().token_type, TT::Identifier(_, ref name) if name == "as")
+    }
+
+    fn variable_declaration(&mut self, keyword: Token) -> Result<Stmt, Error> {
         let name = self.expect_identifier(
             "Expect variable name.".to_string()
         )?;
@@ -182,38 +352,65 @@ impl Parser {
             "Expect ';' after variable declaration.".to_string(),
         )?;
 
-        Ok(Stmt::Var(name, initializer))
+        Ok(Stmt::Var(self.since(&keyword), name, initializer))
     }
 
     fn statement(&mut self) -> Result<Stmt, Error> {
-        if self.advance_if(&[TT::For]).is_some() {
-            return self.for_statement();
+        if let Some(keyword) = self.advance_if(&[TT::Break]) {
+            return self.break_statement(keyword);
         }
 
-        if self.advance_if(&[TT::If]).is_some() {
-            return self.if_statement();
+        if let Some(keyword) = self.advance_if(&[TT::Continue]) {
+            return self.continue_statement(keyword);
         }
 
-        if self.advance_if(&[TT::LeftBrace]).is_some() {
-            return Ok(Stmt::Block(self.block()?));
+        if let Some(keyword) = self.advance_if(&[TT::For]) {
+            return self.for_statement(keyword);
+        }
+
+        if let Some(keyword) = self.advance_if(&[TT::If]) {
+            return self.if_statement(keyword);
+        }
+
+        if let Some(brace) = self.advance_if(&[TT::LeftBrace]) {
+            let statements = self.block()?;
+            return Ok(Stmt::Block(self.since(&brace), statements));
         }
 
-        if self.advance_if(&[TT::Print]).is_some() {
-            return self.print_statement();
+        if let Some(keyword) = self.advance_if(&[TT::Print]) {
+            return self.print_statement(keyword);
         }
 
         if let Some(keyword) = self.advance_if(&[TT::Return]) {
             return self.return_statement(keyword);
         }
 
-        if self.advance_if(&[TT::While]).is_some() {
-            return self.while_statement();
+        if let Some(keyword) = self.advance_if(&[TT::Throw]) {
+            return self.throw_statement(keyword);
+        }
+
+        if let Some(keyword) = self.advance_if(&[TT::Try]) {
+            return self.try_statement(keyword);
+        }
+
+        if let Some(keyword) = self.advance_if(&[TT::While]) {
+            return self.while_statement(keyword);
         }
 
         self.expression_statement()
     }
 
-    fn if_statement(&mut self) -> Result<Stmt, Error> {
+    fn break_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
+        self.expect(TT::Semicolon, "Expect ';' after 'break'.".to_string())?;
+        Ok(Stmt::Break(self.since(&keyword), keyword))
+    }
+
+    fn continue_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
+        self.expect(TT::Semicolon, "Expect ';' after 'continue'.".to_string())?;
+        Ok(Stmt::Continue(self.since(&keyword), keyword))
+    }
+
+    fn if_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
         self.expect(TT::LeftParen, "Expect '(' after 'if'.".to_string())?;
         let condition = self.expression()?;
         self.expect(TT::RightParen, "Expect ')' after condition.".to_string())?;
@@ -226,17 +423,17 @@ impl Parser {
             None
         };
 
-        Ok(Stmt::If(condition, then_branch, else_branch))
+        Ok(Stmt::If(self.since(&keyword), condition, then_branch, else_branch))
     }
 
-    fn for_statement(&mut self) -> Result<Stmt, Error> {
+    fn for_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
         self.expect(TT::LeftParen, "Expect '(' after 'for'.".to_string())?;
 
         let initializer: Option<Stmt> =
             if self.advance_if(&[TT::Semicolon]).is_some() {
                 None
-            } else if self.advance_if(&[TT::Var]).is_some() {
-                Some(self.variable_declaration()?)
+            } else if let Some(keyword) = self.advance_if(&[TT::Var]) {
+                Some(self.variable_declaration(keyword)?)
             } else {
                 Some(self.expression_statement()?)
             };
@@ -245,7 +442,10 @@ impl Parser {
             if self.check(&TT::Semicolon) { None }
             else { Some(self.expression()?) };
 
-        self.expect(TT::Semicolon, "Expect ';' after loop condition.".to_string())?;
+        let semicolon = self.expect(
+            TT::Semicolon,
+            "Expect ';' after loop condition.".to_string()
+        )?;
 
         let increment: Option<Expr> =
             if self.check(&TT::RightParen) { None }
@@ -253,20 +453,20 @@ impl Parser {
 
         self.expect(TT::RightParen, "Expect ')' after for clauses.".to_string())?;
 
-        let mut body: Stmt = self.statement()?;
-
-        if let Some(increment) = increment {
-            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
-        }
+        let body: Stmt = self.statement()?;
 
+        // A missing condition is always true. Blame its semicolon.
         let condition: Expr = condition.unwrap_or(
-            Expr::Literal(Object::Boolean(true))
+            Expr::Literal(semicolon.span, Object::Boolean(true))
         );
 
-        body = Stmt::While(condition, Box::new(body));
+        // The increment stays out of the body so that 'continue' skips to it
+        // instead of over it.
+        let span = self.since(&keyword);
+        let mut body = Stmt::While(span, condition, Box::new(body), increment);
 
         if let Some(initializer) = initializer {
-            body = Stmt::Block(vec![initializer, body]);
+            body = Stmt::Block(span, vec![initializer, body]);
         }
 
         Ok(body)
@@ -286,10 +486,10 @@ impl Parser {
         Ok(statements)
     }
 
-    fn print_statement(&mut self) -> Result<Stmt, Error> {
+    fn print_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
         let value: Expr = self.expression()?;
         self.expect(TT::Semicolon, "Expect ';' after value.".to_string())?;
-        Ok(Stmt::Print(value))
+        Ok(Stmt::Print(self.since(&keyword), value))
     }
 
     fn return_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
@@ -300,21 +500,58 @@ impl Parser {
         };
     
         self.expect(TT::Semicolon, "Expect ';' after return value.".to_string())?;
-        Ok(Stmt::Return(keyword, value))
+        Ok(Stmt::Return(self.since(&keyword), keyword, value))
+    }
+
+    fn throw_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
+        let value: Expr = self.expression()?;
+        self.expect(TT::Semicolon, "Expect ';' after thrown value.".to_string())?;
+        Ok(Stmt::Throw(self.since(&keyword), keyword, value))
     }
 
-    fn while_statement(&mut self) -> Result<Stmt, Error> {
+    fn try_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
+        self.expect(TT::LeftBrace, "Expect '{' after 'try'.".to_string())?;
+        let body = self.block()?;
+
+        let catch = if self.advance_if(&[TT::Catch]).is_some() {
+            self.expect(TT::LeftParen, "Expect '(' after 'catch'.".to_string())?;
+            let name = self.expect_identifier("Expect exception variable name.".to_string())?;
+            self.expect(TT::RightParen, "Expect ')' after exception variable.".to_string())?;
+            self.expect(TT::LeftBrace, "Expect '{' before catch body.".to_string())?;
+            Some((name, self.block()?))
+        } else {
+            None
+        };
+
+        let finally = if self.advance_if(&[TT::Finally]).is_some() {
+            self.expect(TT::LeftBrace, "Expect '{' after 'finally'.".to_string())?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(Error::new(
+                Token::clone(self.peek()),
+                "Expect 'catch' or 'finally' after try block.".to_string()
+            ));
+        }
+
+        Ok(Stmt::Try(self.since(&keyword), body, catch, finally))
+    }
+
+    fn while_statement(&mut self, keyword: Token) -> Result<Stmt, Error> {
         self.expect(TT::LeftParen, "Expect '(' after 'while'.".to_string())?;
         let condition = self.expression()?;
         self.expect(TT::RightParen, "Expect ')' after condition.".to_string())?;
         let body = Box::new(self.statement()?);
-        Ok(Stmt::While(condition, body))
+        Ok(Stmt::While(self.since(&keyword), condition, body, None))
     }
 
     fn expression_statement(&mut self) -> Result<Stmt, Error> {
         let expression: Expr = self.expression()?;
         self.expect(TT::Semicolon, "Expect ';' after expression.".to_string())?;
-        Ok(Stmt::Expression(expression))
+        Ok(Stmt::Expression(expression.span().to(self.previous), expression))
     }
 
     fn expression(&mut self) -> Result<Expr, Error> {
@@ -332,9 +569,10 @@ impl Parser {
                     Ok(Expr::Assignment(name, Box::new(value))),
                 Expr::Get(object, name) =>
                     Ok(Expr::Set(object, name, Box::new(value))),
+                Expr::Index(object, bracket, index) =>
+                    Ok(Expr::SetIndex(object, bracket, index, Box::new(value))),
                 _ => {
-                    error::parse_error(&equals, "Invalid assignment target.");
-                    self.stumbled = true;
+                    self.stumble(equals, "Invalid assignment target.");
                     Ok(value) // [1]
                 }
             };
@@ -369,7 +607,7 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Result<Expr, Error> {
-        let operators = [TT::Slash, TT::Star];
+        let operators = [TT::Percent, TT::Slash, TT::Star, TT::TildeSlash];
         self.binary(&operators, &Parser::unary, &Expr::Binary)
     }
 
@@ -384,7 +622,22 @@ impl Parser {
             return Ok(Expr::Unary(operator, Box::new(right)));
         }
 
-        self.call()
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, Error> {
+        // Exponentiation binds tighter than a unary operator on its left, so
+        // -2 ** 2 is -4, and it's right-associative because its exponent is a
+        // unary expression, which may itself be a power.
+
+        let base = self.call()?;
+
+        if let Some(operator) = self.advance_if(&[TT::StarStar]) {
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(Box::new(base), operator, Box::new(exponent)));
+        }
+
+        Ok(base)
     }
 
     fn call(&mut self) -> Result<Expr, Error> {
@@ -398,6 +651,13 @@ impl Parser {
                     "Expect property name after '.'.".to_string()
                 )?;
                 expr = Expr::Get(Box::new(expr), name);
+            } else if self.advance_if(&[TT::LeftBracket]).is_some() {
+                let index = self.expression()?;
+                let bracket = self.expect(
+                    TT::RightBracket,
+                    "Expect ']' after index.".to_string()
+                )?;
+                expr = Expr::Index(Box::new(expr), bracket, Box::new(index));
             } else {
                 break;
             }
@@ -416,11 +676,8 @@ impl Parser {
             while self.advance_if(&[TT::Comma]).is_some() {
                 if !too_many && arguments.len() >= 255 {
                     too_many = true;
-                    error::parse_error(
-                        self.peek(),
-                        "Can't have more than 255 arguments."
-                    );
-                    self.stumbled = true;
+                    let next = Token::clone(self.peek());
+                    self.stumble(next, "Can't have more than 255 arguments.");
                 }
                 arguments.push(self.expression()?);
             }
@@ -441,6 +698,13 @@ impl Parser {
     }
 
     fn primary(&mut self) -> Result<Expr, Error> {
+        // A named function is a declaration, not an expression.
+
+        if self.check(&TT::Fun) && self.check_next(&TT::LeftParen) {
+            let keyword = self.advance();
+            return self.lambda(keyword);
+        }
+
         let next = self.peek();
 
         if let TT::Identifier(..) = next.token_type {
@@ -451,14 +715,24 @@ impl Parser {
             |  TT::Number(_) | TT::String(_)
             |  TT::Nil
             = next.token_type {
-            return Ok(Expr::Literal(to_object(self.advance())));
+            let literal = self.advance();
+            return Ok(Expr::Literal(literal.span, to_object(literal)));
+        }
+
+        if let TT::Interpolation(_) = next.token_type {
+            return self.interpolation();
         }
 
         if let TT::LeftParen = next.token_type {
-            self.advance();
+            let left = self.advance();
             let group: Expr = self.expression()?;
-            self.expect(TT::RightParen, "Expect ')' after expression.".to_string())?;
-            return Ok(Expr::Grouping(Box::new(group)));
+            let right = self.expect(TT::RightParen, "Expect ')' after expression.".to_string())?;
+            return Ok(Expr::Grouping(left.span.to(right.span), Box::new(group)));
+        }
+
+        if let TT::LeftBracket = next.token_type {
+            let left = self.advance();
+            return self.collection(left);
         }
 
         if let TT::This(..) = next.token_type {
@@ -478,6 +752,116 @@ impl Parser {
         ))
     }
 
+    fn interpolation(&mut self) -> Result<Expr, Error> {
+        // Segments and interpolated expressions alternate until the string
+        // that ends the literal.
+
+        let mut parts = Vec::new();
+        let first = self.peek().span;
+
+        loop {
+            let segment = self.advance();
+            let span = segment.span;
+
+            let (text, last) = match segment.token_type {
+                TT::Interpolation(text) => (text, false),
+                TT::String(text) => (text, true),
+                // A panic here indicates an error in the parser.
+                _ => panic!("token is not a string segment"),
+            };
+
+            parts.push(Expr::Literal(span, Object::String(text)));
+
+            if last {
+                return Ok(Expr::Interpolation(first.to(span), parts));
+            }
+
+            // A segment that picks up after an interpolation starts with its
+            // closing brace, so it can't be mistaken for a string literal.
+
+            if self.peek().lexeme.starts_with('}') {
+                return Err(Error::new(
+                    Token::clone(self.peek()),
+                    "Expect expression in interpolation.".to_string()
+                ));
+            }
+
+            parts.push(self.expression()?);
+
+            if let TT::Interpolation(_) | TT::String(_) = self.peek().token_type {
+                continue;
+            }
+
+            return Err(Error::new(
+                Token::clone(self.peek()),
+                "Expect '}' after interpolated expression.".to_string()
+            ));
+        }
+    }
+
+    fn collection(&mut self, left: Token) -> Result<Expr, Error> {
+        // Lists and maps share brackets, so that a brace in an expression
+        // never competes with a block. A map's first key is followed by a
+        // colon, and the empty map is [:].
+
+        if self.advance_if(&[TT::Colon]).is_some() {
+            let right = self.expect(TT::RightBracket, "Expect ']' after ':' in empty map.".to_string())?;
+            return Ok(Expr::Map(left.span.to(right.span), Vec::new()));
+        }
+
+        if let Some(right) = self.advance_if(&[TT::RightBracket]) {
+            return Ok(Expr::List(left.span.to(right.span), Vec::new()));
+        }
+
+        let first = self.expression()?;
+
+        if let Some(colon) = self.advance_if(&[TT::Colon]) {
+            let entries = self.entries(first, colon)?;
+            let right = self.expect(TT::RightBracket, "Expect ']' after map entries.".to_string())?;
+            return Ok(Expr::Map(left.span.to(right.span), entries));
+        }
+
+        let elements = self.elements(first)?;
+        let right = self.expect(TT::RightBracket, "Expect ']' after list elements.".to_string())?;
+        Ok(Expr::List(left.span.to(right.span), elements))
+    }
+
+    fn elements(&mut self, first: Expr) -> Result<Vec<Expr>, Error> {
+        // A list literal may end with a trailing comma.
+
+        let mut elements = vec![first];
+
+        while self.advance_if(&[TT::Comma]).is_some() {
+            if self.check(&TT::RightBracket) { break; }
+            elements.push(self.expression()?);
+        }
+
+        Ok(elements)
+    }
+
+    fn entries(
+        &mut self,
+        key: Expr, colon: Token
+    ) -> Result<Vec<(Expr, Token, Expr)>, Error> {
+        // A map literal may end with a trailing comma. The colon is kept to
+        // blame for a key that can't be hashed.
+
+        let value = self.expression()?;
+        let mut entries = vec![(key, colon, value)];
+
+        while self.advance_if(&[TT::Comma]).is_some() {
+            if self.check(&TT::RightBracket) { break; }
+
+            let key = self.expression()?;
+            let colon = self.expect(TT::Colon, "Expect ':' after map key.".to_string())?;
+            let value = self.expression()?;
+
+            entries.push((key, colon, value));
+        }
+
+        Ok(entries)
+    }
+
     fn binary<E, O>(
         &mut self,
         operators: &[TT], operand: &O, expression: &E
@@ -503,7 +887,7 @@ impl Parser {
     }
 
     fn peek(&mut self) -> &Token {
-        if let Some(next) = self.tokens.peek() {
+        if let Some(next) = self.tokens.last() {
             return next;
         }
 
@@ -517,8 +901,18 @@ impl Parser {
         self.peek().token_type == *token_type
     }
 
+    fn check_next(&mut self, token_type: &TT) -> bool {
+        // Look at the token after the next one.
+
+        match self.tokens.iter().rev().nth(1) {
+            Some(next) => next.token_type == *token_type,
+            None => false,
+        }
+    }
+
     fn advance(&mut self) -> Token {
-        if let Some(previous) = self.tokens.next() {
+        if let Some(previous) = self.tokens.pop() {
+            self.previous = previous.span;
             return previous;
         }
 
@@ -526,6 +920,11 @@ impl Parser {
         panic!("expect EOF token at end");
     }
 
+    fn since(&self, start: &Token) -> Span {
+        // From a statement's first token to the last one parsed.
+        start.span.to(self.previous)
+    }
+
     fn advance_if(&mut self, token_types: &[TT]) -> Option<Token> {
         for token_type in token_types {
             if self.check(token_type) {
@@ -560,6 +959,10 @@ impl Parser {
         ))
     }
 
+    fn stumble(&mut self, at: Token, reason: &str) {
+        self.diagnostics.push(Diagnostic::parse_error(&at, reason));
+    }
+
     fn synchronize(&mut self) {
         while !self.is_at_end() {
             // If the current Token is a semicolon, the next Token starts a new
@@ -578,8 +981,10 @@ impl Parser {
             // the gate. Mine does. I can't advance on the EOF token without
             // exhausting the token iterator and causing a panic.
 
-            if let TT::Class  | TT::For | TT::Fun   | TT::If | TT::Print
-                |  TT::Return | TT::Var | TT::While
+            if let TT::Class  | TT::For      | TT::Fun    | TT::If
+                |  TT::Print  | TT::Return   | TT::Var    | TT::While
+                |  TT::Break  | TT::Continue | TT::Import | TT::Throw
+                |  TT::Try
                 = self.peek().token_type { return; }
 
             self.advance();
@@ -597,3 +1002,96 @@ impl Parser {
 // though. Bob's implementation kicks up the malformed assignment target but
 // never evaluates the AST. Mine kicks up the malformed target's value and also
 // doesn't evaluate the AST after the parser recovers from a stumble.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(source: &str) -> Vec<Stmt> {
+        let mut scanner = Scanner::new(source, 0);
+        scanner.scan_tokens();
+        let (tokens, _) = scanner.consume();
+
+        let mut parser = Parser::new(tokens);
+        parser.parse();
+        parser.consume().ok().unwrap()
+    }
+
+    fn text(source: &str, span: Span) -> &str {
+        &source[span.start..span.end]
+    }
+
+    #[test]
+    fn expression_spans() {
+        let source = "-(1 + 2) * 3;\n\
+                      bagel.toast[0](crumbs, butter);\n\
+                      spread = jam or \"marmalade ${flavor}\";\n\
+                      fun (slice) { return slice; };\n\
+                      [\"toppings\": [seeds, salt]];\n\
+                      super.bake;\n\
+                      loaf.crust = crumb[1] = 2;";
+
+        let spans: Vec<&str> = parse(source).iter().map(|statement| {
+            match statement {
+                Stmt::Expression(_, expression) =>
+                    text(source, expression.span()),
+                _ => panic!("expected an expression statement"),
+            }
+        }).collect();
+
+        assert_eq!(spans, vec![
+            "-(1 + 2) * 3",
+            "bagel.toast[0](crumbs, butter)",
+            "spread = jam or \"marmalade ${flavor}\"",
+            "fun (slice) { return slice; }",
+            "[\"toppings\": [seeds, salt]]",
+            "super.bake",
+            "loaf.crust = crumb[1] = 2",
+        ]);
+    }
+
+    #[test]
+    fn statement_spans() {
+        let source = "print \"crepe\";\n\
+                      var a = [1, 2];\n\
+                      if (a) { print a; } else print nil;\n\
+                      while (false) a = 1;\n\
+                      for (var i = 0; i < 1; i = i + 1) {}\n\
+                      fun f(x) {\n  return x;\n}\n\
+                      class Bagel < Bread { toast() {} }\n\
+                      try { throw \"oops\"; } catch (e) {} finally {}\n\
+                      { break; continue; }\n\
+                      import \"util.lox\" as u;\n\
+                      f(1);";
+
+        let statements = parse(source);
+        let spans: Vec<&str> = statements.iter().map(
+            |statement| text(source, statement.span())
+        ).collect();
+
+        assert_eq!(spans, vec![
+            "print \"crepe\";",
+            "var a = [1, 2];",
+            "if (a) { print a; } else print nil;",
+            "while (false) a = 1;",
+            "for (var i = 0; i < 1; i = i + 1) {}",
+            "fun f(x) {\n  return x;\n}",
+            "class Bagel < Bread { toast() {} }",
+            "try { throw \"oops\"; } catch (e) {} finally {}",
+            "{ break; continue; }",
+            "import \"util.lox\" as u;",
+            "f(1);",
+        ]);
+
+        // Nested statements have their own spans.
+        let nested: Vec<&str> = match &statements[8] {
+            Stmt::Block(_, block) => block.iter().map(
+                |statement| text(source, statement.span())
+            ).collect(),
+            _ => panic!("expected a block"),
+        };
+
+        assert_eq!(nested, vec!["break;", "continue;"]);
+    }
+}
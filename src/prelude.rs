@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::callable::{self as call, Arity, Callable, Halt, Native};
+use crate::list::{self, List};
+use crate::map;
+use crate::object::Object;
+
+// The natives an interpreter can start with, in groups. A host that runs
+// untrusted scripts can start from a bare session and load only the groups it
+// trusts, or none at all. Input and output aren't loaded by default because
+// they reach outside of the interpreter: the file system, standard input, and
+// the host itself.
+
+// Where 'readLine' and 'input' read from. Standard input, unless the host
+// supplies its own reader. It's shared so that a host can swap it after the
+// natives are loaded.
+
+pub type Input = Rc<RefCell<Option<Box<dyn BufRead>>>>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Library {
+    Time,
+    Math,
+    Strings,
+    Conversion,
+    Collections,
+    Io,
+}
+
+impl Library {
+    pub const DEFAULT: [Library; 5] = [
+        Library::Time,
+        Library::Math,
+        Library::Strings,
+        Library::Conversion,
+        Library::Collections,
+    ];
+
+    pub fn natives(&self, input: &Input) -> Vec<Native> {
+        match self {
+            Library::Time => vec![
+                Native::new("clock", Arity::Fixed(0), call::clock),
+            ],
+            Library::Math => vec![
+                Native::new("abs", Arity::Fixed(1), abs),
+                Native::new("floor", Arity::Fixed(1), floor),
+                Native::new("max", Arity::Fixed(2), max),
+                Native::new("min", Arity::Fixed(2), min),
+                Native::new("sqrt", Arity::Fixed(1), sqrt),
+            ],
+            Library::Strings => vec![
+                Native::new("chr", Arity::Fixed(1), chr),
+                Native::new("lower", Arity::Fixed(1), lower),
+                Native::new("ord", Arity::Fixed(1), ord),
+                Native::new("split", Arity::Fixed(2), split),
+                Native::new("substring", Arity::Fixed(3), substring),
+                Native::new("upper", Arity::Fixed(1), upper),
+            ],
+            Library::Conversion => vec![
+                Native::new("num", Arity::Fixed(1), num),
+                Native::new("str", Arity::Fixed(1), str),
+                Native::new("type", Arity::Fixed(1), type_of),
+            ],
+            Library::Collections => vec![
+                Native::new("has", Arity::Fixed(2), map::has),
+                Native::new("keys", Arity::Fixed(1), map::keys),
+                Native::new("len", Arity::Fixed(1), list::len),
+                Native::new("pop", Arity::Fixed(1), list::pop),
+                Native::new("push", Arity::Fixed(2), list::push),
+                Native::new("remove", Arity::Fixed(2), map::remove),
+            ],
+            Library::Io => vec![
+                Native::new("appendFile", Arity::Fixed(2), append_file),
+                Native::halting("exit", Arity::Fixed(1), exit),
+                Native::new("fileExists", Arity::Fixed(1), file_exists),
+                Native::new("input", Arity::Fixed(0), line_reader(input)),
+                Native::new("readFile", Arity::Fixed(1), read_file),
+                Native::new("readLine", Arity::Fixed(0), line_reader(input)),
+                Native::new("writeFile", Arity::Fixed(2), write_file),
+            ],
+        }
+    }
+}
+
+fn number(object: &Object) -> Result<f64, String> {
+    match object {
+        Object::Number(number) => Ok(*number),
+        _ => Err("Argument must be a number.".to_string()),
+    }
+}
+
+fn integer(object: &Object) -> Result<f64, String> {
+    match object {
+        Object::Number(number) if number.fract() == 0.0 => Ok(*number),
+        _ => Err("Argument must be an integer.".to_string()),
+    }
+}
+
+fn string(object: &Object) -> Result<&str, String> {
+    match object {
+        Object::String(string) => Ok(string),
+        _ => Err("Argument must be a string.".to_string()),
+    }
+}
+
+// Math.
+
+fn abs(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Number(number(&arguments[0])?.abs()))
+}
+
+fn floor(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Number(number(&arguments[0])?.floor()))
+}
+
+fn max(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Number(number(&arguments[0])?.max(number(&arguments[1])?)))
+}
+
+fn min(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Number(number(&arguments[0])?.min(number(&arguments[1])?)))
+}
+
+fn sqrt(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Number(number(&arguments[0])?.sqrt()))
+}
+
+// Strings. Indices count characters, not bytes.
+
+fn chr(arguments: Vec<Object>) -> Result<Object, String> {
+    let code = integer(&arguments[0])?;
+
+    u32::try_from(code as i64).ok().and_then(char::from_u32).map_or_else(
+        || Err("Argument must be a character code.".to_string()),
+        |c| Ok(Object::String(c.to_string()))
+    )
+}
+
+fn lower(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::String(string(&arguments[0])?.to_lowercase()))
+}
+
+fn ord(arguments: Vec<Object>) -> Result<Object, String> {
+    let mut chars = string(&arguments[0])?.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Object::Number(c as u32 as f64)),
+        _ => Err("Argument must be a single character.".to_string()),
+    }
+}
+
+fn split(arguments: Vec<Object>) -> Result<Object, String> {
+    let string = string(&arguments[0])?;
+    let separator = self::string(&arguments[1])?;
+
+    // An empty separator splits a string into its characters.
+
+    let pieces: Vec<Object> = if separator.is_empty() {
+        string.chars().map(|c| Object::String(c.to_string())).collect()
+    } else {
+        string.split(separator).map(|s| Object::String(s.to_string())).collect()
+    };
+
+    Ok(Object::List(List::new(pieces)))
+}
+
+fn substring(arguments: Vec<Object>) -> Result<Object, String> {
+    let string = string(&arguments[0])?;
+    let start = integer(&arguments[1])?;
+    let end = integer(&arguments[2])?;
+
+    let length = string.chars().count() as f64;
+
+    if start < 0.0 || start > end || end > length {
+        return Err("Substring out of range.".to_string());
+    }
+
+    let substring = string.chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect();
+
+    Ok(Object::String(substring))
+}
+
+fn upper(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::String(string(&arguments[0])?.to_uppercase()))
+}
+
+// Conversion.
+
+fn num(arguments: Vec<Object>) -> Result<Object, String> {
+    // Accept the same numbers the scanner does, with an optional sign. Anything
+    // else is nil rather than an error so scripts can validate input.
+
+    let string = match &arguments[0] {
+        Object::Number(number) => return Ok(Object::Number(*number)),
+        object => string(object)?.trim(),
+    };
+
+    let digits = string.strip_prefix('-').unwrap_or(string);
+    let mut parts = digits.splitn(2, '.');
+
+    let is_digits = |part: &str|
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+
+    let is_number = parts.next().is_some_and(is_digits)
+        && parts.next().is_none_or(is_digits);
+
+    Ok(if is_number {
+        string.parse().map_or(Object::Nil, Object::Number)
+    } else { Object::Nil })
+}
+
+fn str(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::String(arguments[0].to_string()))
+}
+
+fn type_of(arguments: Vec<Object>) -> Result<Object, String> {
+    let name = match &arguments[0] {
+        Object::Boolean(_) => "boolean",
+        Object::Callable(Callable::Class(_)) => "class",
+        Object::Callable(_) => "function",
+        Object::Instance(_) => "instance",
+        Object::List(_) => "list",
+        Object::Map(_) => "map",
+        Object::Module(_) => "module",
+        Object::Nil => "nil",
+        Object::Number(_) => "number",
+        Object::String(_) => "string",
+    };
+
+    Ok(Object::String(name.to_string()))
+}
+
+// Input and output. Paths are relative to the working directory of the host.
+
+fn append_file(arguments: Vec<Object>) -> Result<Object, String> {
+    let path = string(&arguments[0])?;
+    let text = string(&arguments[1])?;
+
+    let written = OpenOptions::new().append(true).create(true).open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+
+    written.map(|_| Object::Nil).map_err(|error| io_error("write", path, error))
+}
+
+fn exit(arguments: Vec<Object>) -> Result<Object, Halt> {
+    let code = integer(&arguments[0]).map_err(Halt::Error)?;
+
+    i32::try_from(code as i64).map_or_else(
+        |_| Err(Halt::Error("Exit code out of range.".to_string())),
+        |code| Err(Halt::Exit(code))
+    )
+}
+
+fn file_exists(arguments: Vec<Object>) -> Result<Object, String> {
+    Ok(Object::Boolean(Path::new(string(&arguments[0])?).is_file()))
+}
+
+fn read_file(arguments: Vec<Object>) -> Result<Object, String> {
+    let path = string(&arguments[0])?;
+
+    fs::read_to_string(path).map(Object::String).map_err(
+        |error| io_error("read", path, error)
+    )
+}
+
+fn line_reader(input: &Input) -> impl Fn(Vec<Object>) -> Result<Object, String> {
+    let input = Rc::clone(input);
+    move |_| read_line(&input)
+}
+
+fn read_line(input: &Input) -> Result<Object, String> {
+    // A line of input without its line ending, or nil at the end.
+
+    let mut line = String::new();
+
+    let read = match input.borrow_mut().as_mut() {
+        Some(reader) => reader.read_line(&mut line),
+        None => io::stdin().lock().read_line(&mut line),
+    };
+
+    match read {
+        Ok(0) => Ok(Object::Nil),
+        Ok(_) => {
+            let trimmed = line.trim_end_matches('\n').trim_end_matches('\r');
+            Ok(Object::String(trimmed.to_string()))
+        },
+        Err(error) => Err(format!("Can't read standard input: {}.", error)),
+    }
+}
+
+fn write_file(arguments: Vec<Object>) -> Result<Object, String> {
+    let path = string(&arguments[0])?;
+    let text = string(&arguments[1])?;
+
+    fs::write(path, text).map(|_| Object::Nil).map_err(
+        |error| io_error("write", path, error)
+    )
+}
+
+fn io_error(verb: &str, path: &str, error: io::Error) -> String {
+    format!("Can't {} file '{}': {}.", verb, path, error)
+}
@@ -1,12 +1,13 @@
 use rustc_hash::FxHashMap;
 
 use crate::callable::definitions as def;
-use crate::error;
+use crate::error::{Diagnostic, Diagnostics, Label};
 use crate::expression::{self as expr, Expr};
 use crate::object::Object;
 use crate::statement::{self as stmt, Stmt};
 use crate::token::Token;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, PartialEq)]
 enum Function {
     Global,
@@ -15,19 +16,31 @@ enum Function {
     Initializer,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, PartialEq)]
 enum Class {
     Global,
     Class,
     Subclass,
+    Static,
+}
+
+// A local variable is declared before it's defined so that its initializer
+// can't refer to it. Its declaration is kept to point at when it's declared
+// twice. Implicit names like 'this' and 'super' don't have one.
+
+struct Local {
+    defined: bool,
+    declaration: Option<Token>,
 }
 
 pub struct Resolver {
-    scopes: Vec<FxHashMap<String, bool>>,
+    scopes: Vec<FxHashMap<String, Local>>,
     resolutions: FxHashMap<usize, usize>,
     function_scope: Function,
     class_scope: Class,
-    stumbled: bool,
+    loop_depth: usize,
+    diagnostics: Diagnostics,
 }
 
 impl Resolver {
@@ -37,15 +50,16 @@ impl Resolver {
             resolutions: FxHashMap::default(),
             function_scope: Function::Global,
             class_scope: Class::Global,
-            stumbled: false,
+            loop_depth: 0,
+            diagnostics: Vec::new(),
         }
     }
 
-    pub fn consume(self) -> Result<FxHashMap<usize, usize>, error::LoxError> {
-        if self.stumbled {
-            Err(error::LoxError::Resolve)
-        } else {
+    pub fn consume(self) -> Result<FxHashMap<usize, usize>, Diagnostics> {
+        if self.diagnostics.is_empty() {
             Ok(self.resolutions)
+        } else {
+            Err(self.diagnostics)
         }
     }
 
@@ -67,14 +81,18 @@ impl Resolver {
         &mut self, definition: &def::Function,
         function_scope: Function,
     ) {
-        let def::Function(_, parameters, body) = definition;
+        let def::Function(_, parameters, body, _) = definition;
         let parameters: &Vec<Token> = parameters;
         let enclosing_function = self.function_scope;
+        let enclosing_loop_depth = self.loop_depth;
 
         self.begin_scope();
 
         self.function_scope = function_scope;
 
+        // A function body can't break out of a loop it's declared in.
+        self.loop_depth = 0;
+
         for parameter in parameters {
             // TODO: It's not technically necessary to declare and define the
             // parameter name. Just a definition would suffice but the
@@ -90,6 +108,7 @@ impl Resolver {
         self.end_scope();
 
         self.function_scope = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
     }
 
     fn resolve_local(&mut self, name: &Token) {
@@ -112,28 +131,55 @@ impl Resolver {
     }
 
     fn declare(&mut self, name: &Token) {
-        if let Some(scope) = self.scopes.last() {
-            if scope.contains_key(name.to_name().1) {
-                self.stumble(name, "Already a variable with this name in this scope.");
-            } else {
-                self.add_to_scope(name.to_name().1, false);
+        let previous = self.scopes.last().and_then(
+            |scope| scope.get(name.to_name().1)
+        ).map(|local| local.declaration.clone());
+
+        match previous {
+            Some(previous) => {
+                let mut diagnostic = Diagnostic::resolve_error(
+                    name, "Already a variable with this name in this scope."
+                );
+
+                if let Some(previous) = previous {
+                    diagnostic = diagnostic.with_label(
+                        Label::new(&previous, "previous declaration here")
+                    );
+                }
+
+                self.diagnostics.push(diagnostic);
+            },
+            None => {
+                let local = Local {
+                    defined: false,
+                    declaration: Some(Token::clone(name)),
+                };
+                self.add_to_scope(name.to_name().1, local);
             }
         }
     }
 
     fn define(&mut self, name: &Token) {
-        self.add_to_scope(name.to_name().1, true)
+        if let Some(scope) = self.scopes.last_mut() {
+            let local = scope.entry(name.to_name().1.to_string()).or_insert(
+                Local { defined: false, declaration: None }
+            );
+            local.defined = true;
+        }
+    }
+
+    fn define_implicit(&mut self, name: &str) {
+        self.add_to_scope(name, Local { defined: true, declaration: None });
     }
 
-    fn add_to_scope(&mut self, name: &str, resolved: bool) {
+    fn add_to_scope(&mut self, name: &str, local: Local) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name.to_string(), resolved);
+            scope.insert(name.to_string(), local);
         }
     }
 
     fn stumble(&mut self, at: &Token, reason: &str) {
-        error::parse_error(at, reason);
-        self.stumbled = true;
+        self.diagnostics.push(Diagnostic::resolve_error(at, reason));
     }
 }
 
@@ -164,6 +210,27 @@ impl expr::Visitor<()> for Resolver {
         self.resolve_expression(expression);
     }
 
+    fn visit_index(&mut self, object: &Expr, _: &Token, index: &Expr) {
+        self.resolve_expression(object);
+        self.resolve_expression(index);
+    }
+
+    fn visit_interpolation(&mut self, parts: &[Expr]) {
+        for part in parts {
+            self.resolve_expression(part);
+        }
+    }
+
+    fn visit_lambda(&mut self, definition: &def::Function) {
+        self.resolve_function(definition, Function::Function);
+    }
+
+    fn visit_list(&mut self, elements: &[Expr]) {
+        for element in elements {
+            self.resolve_expression(element);
+        }
+    }
+
     fn visit_literal(&mut self, _: &Object) { }
 
     fn visit_logical(&mut self, left: &Expr, _: &Token, right: &Expr) {
@@ -171,14 +238,32 @@ impl expr::Visitor<()> for Resolver {
         self.resolve_expression(right);
     }
 
+    fn visit_map(&mut self, entries: &[(Expr, Token, Expr)]) {
+        for (key, _, value) in entries {
+            self.resolve_expression(key);
+            self.resolve_expression(value);
+        }
+    }
+
     fn visit_set(&mut self, object: &Expr, _: &Token, value: &Expr) {
         self.resolve_expression(value);
         self.resolve_expression(object);
     }
 
+    fn visit_set_index(
+        &mut self, object: &Expr, _: &Token,
+        index: &Expr, value: &Expr
+    ) {
+        self.resolve_expression(object);
+        self.resolve_expression(index);
+        self.resolve_expression(value);
+    }
+
     fn visit_super(&mut self, keyword: &Token, _: &Token) {
         if self.class_scope == Class::Global {
             self.stumble(keyword, "Can't use 'super' outside of a class.");
+        } else if self.class_scope == Class::Static {
+            self.stumble(keyword, "Can't use 'super' in a static method.");
         } else if self.class_scope == Class::Class {
             self.stumble(keyword, "Can't use 'super' in a class with no superclass.");
         }
@@ -189,6 +274,8 @@ impl expr::Visitor<()> for Resolver {
     fn visit_this(&mut self, this: &Token) {
         if self.class_scope == Class::Global {
             self.stumble(this, "Can't use 'this' outside of a class.");
+        } else if self.class_scope == Class::Static {
+            self.stumble(this, "Can't use 'this' in a static method.");
         }
 
         self.resolve_local(this);
@@ -200,7 +287,7 @@ impl expr::Visitor<()> for Resolver {
 
     fn visit_variable(&mut self, name: &Token) {
         if let Some(scope) = self.scopes.last() {
-            if let Some(false) = scope.get(name.to_name().1) {
+            if let Some(Local { defined: false, .. }) = scope.get(name.to_name().1) {
                 self.stumble(name, "Can't read local variable in its own initializer.");
             }
 
@@ -216,8 +303,14 @@ impl stmt::Visitor<()> for Resolver {
         self.end_scope();
     }
 
+    fn visit_break(&mut self, keyword: &Token) {
+        if self.loop_depth == 0 {
+            self.stumble(keyword, "Can't use 'break' outside of a loop.");
+        }
+    }
+
     fn visit_class(&mut self, definition: &def::Class) {
-        let def::Class(name, parent, methods) = definition;
+        let def::Class(name, parent, methods, statics) = definition;
 
         let enclosing_class = self.class_scope;
         self.class_scope = Class::Class;
@@ -235,14 +328,26 @@ impl stmt::Visitor<()> for Resolver {
             self.resolve_local(parent);
         }
 
+        // Static methods are resolved outside of the scopes of 'this' and
+        // 'super', like the interpreter defines them.
+
+        let class_scope = self.class_scope;
+        self.class_scope = Class::Static;
+
+        for method in statics {
+            self.resolve_function(method, Function::Function);
+        }
+
+        self.class_scope = class_scope;
+
         if parent.is_some() {
             self.begin_scope();
-            self.add_to_scope("super", true);
+            self.define_implicit("super");
         }
 
         self.begin_scope();
 
-        self.add_to_scope("this", true);
+        self.define_implicit("this");
 
         for method in methods {
             let def::Function(name, ..) = method;
@@ -261,6 +366,12 @@ impl stmt::Visitor<()> for Resolver {
         self.class_scope = enclosing_class;
     }
 
+    fn visit_continue(&mut self, keyword: &Token) {
+        if self.loop_depth == 0 {
+            self.stumble(keyword, "Can't use 'continue' outside of a loop.");
+        }
+    }
+
     fn visit_expression(&mut self, expression: &Expr) {
         self.resolve_expression(expression)
     }
@@ -286,6 +397,11 @@ impl stmt::Visitor<()> for Resolver {
         }
     }
 
+    fn visit_import(&mut self, _: &Token, _: &str, name: &Token) {
+        self.declare(name);
+        self.define(name);
+    }
+
     fn visit_print(&mut self, object: &Expr) {
         self.resolve_expression(object);
     }
@@ -304,6 +420,31 @@ impl stmt::Visitor<()> for Resolver {
         }
     }
 
+    fn visit_throw(&mut self, _: &Token, object: &Expr) {
+        self.resolve_expression(object);
+    }
+
+    fn visit_try(
+        &mut self, body: &[Stmt],
+        catch: &Option<(Token, Vec<Stmt>)>, finally: &Option<Vec<Stmt>>
+    ) {
+        self.visit_block(body);
+
+        // The exception variable shares a scope with the catch body.
+
+        if let Some((name, body)) = catch {
+            self.begin_scope();
+            self.declare(name);
+            self.define(name);
+            self.resolve_statements(body);
+            self.end_scope();
+        }
+
+        if let Some(body) = finally {
+            self.visit_block(body);
+        }
+    }
+
     fn visit_var(&mut self, name: &Token, object: &Option<Expr>) {
         self.declare(name);
 
@@ -314,8 +455,18 @@ impl stmt::Visitor<()> for Resolver {
         self.define(name);
     }
 
-    fn visit_while(&mut self, condition: &Expr, body: &Stmt) {
+    fn visit_while(
+        &mut self, condition: &Expr,
+        body: &Stmt, increment: &Option<Expr>
+    ) {
         self.resolve_expression(condition);
+
+        self.loop_depth += 1;
         self.resolve_statement(body);
+        self.loop_depth -= 1;
+
+        if let Some(increment) = increment {
+            self.resolve_expression(increment);
+        }
     }
 }
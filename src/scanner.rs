@@ -1,5 +1,5 @@
-use crate::error;
-use crate::token::Token;
+use crate::error::{Diagnostic, Diagnostics};
+use crate::token::{Span, Token};
 use crate::token_type::TokenType as TT;
 
 pub struct Scanner {
@@ -7,76 +7,127 @@ pub struct Scanner {
     tokens: Vec<Token>,
     start: usize,
     current: usize,
+    start_byte: usize,
+    current_byte: usize,
     line: usize,
+    line_start: usize,
+    column: usize,
     identifier_key: usize,
-    stumbled: bool,
+    diagnostics: Diagnostics,
+    unterminated: bool,
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
-    pub fn new(source: &str) -> Scanner {
+    pub fn new(source: &str, identifier_key: usize) -> Scanner {
+        // Identifier keys index the resolver's resolutions. A caller that
+        // scans more than one source for the same interpreter, like the
+        // prompt, must resume counting where the last scanner left off.
+
         Scanner {
             source: source.chars().collect(), // [1]
             tokens: Vec::new(),
             start: 0,
             current: 0,
+            start_byte: 0,
+            current_byte: 0,
             line: 1,
-            identifier_key: 0,
-            stumbled: false,
+            line_start: 0,
+            column: 1,
+            identifier_key,
+            diagnostics: Vec::new(),
+            unterminated: false,
+            interpolations: Vec::new(),
         }
     }
 
     pub fn scan_tokens(&mut self) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_byte = self.current_byte;
+            self.column = self.current - self.line_start + 1;
             self.scan_token();
         }
 
+        if !self.interpolations.is_empty() {
+            self.error("Unterminated string interpolation.");
+            self.unterminated = true;
+        }
+
         let end_of_file = Token::new(
             TT::EndOfFile,
             String::from("\0"), // [2]
-            self.line
+            self.line,
+            self.current - self.line_start + 1,
+            Span::new(self.current_byte, self.current_byte)
         );
 
         self.tokens.push(end_of_file);
     }
 
-    pub fn consume(self) -> Result<Vec<Token>, error::LoxError> {
-        if self.stumbled {
-            Err(error::LoxError::Scan)
-        } else {
-            Ok(self.tokens)
+    pub fn identifier_key(&self) -> usize {
+        self.identifier_key
+    }
+
+    pub fn is_unfinished(&self) -> bool {
+        // The source may continue on another line if it ends inside a string
+        // or a block comment, or leaves a parenthesis, brace, or bracket open.
+        // The prompt uses this to keep reading instead of reporting an error.
+
+        let mut depth: isize = 0;
+
+        for token in &self.tokens {
+            match token.token_type {
+                TT::LeftParen  | TT::LeftBrace  | TT::LeftBracket  => depth += 1,
+                TT::RightParen | TT::RightBrace | TT::RightBracket => depth -= 1,
+                _ => (),
+            }
         }
+
+        self.unterminated || depth > 0
+    }
+
+    pub fn consume(self) -> (Vec<Token>, Diagnostics) {
+        // The scanner always produces tokens, skipping over anything it can't
+        // make sense of, so the parser can report its own errors too.
+
+        (self.tokens, self.diagnostics)
     }
 
     fn scan_token(&mut self) {
         match self.advance() {
             '(' => self.add_token(TT::LeftParen),
             ')' => self.add_token(TT::RightParen),
-            '{' => self.add_token(TT::LeftBrace),
-            '}' => self.add_token(TT::RightBrace),
+            '{' => self.left_brace(),
+            '}' => self.right_brace(),
+            '[' => self.add_token(TT::LeftBracket),
+            ']' => self.add_token(TT::RightBracket),
+            ':' => self.add_token(TT::Colon),
             ',' => self.add_token(TT::Comma),
             '.' => self.add_token(TT::Dot),
             '-' => self.add_token(TT::Minus),
             '+' => self.add_token(TT::Plus),
             ';' => self.add_token(TT::Semicolon),
-            '*' => self.add_token(TT::Star),
+            '%' => self.add_token(TT::Percent),
 
             '!' => self.add_token_if('=', TT::BangEqual, TT::Bang),
             '=' => self.add_token_if('=', TT::EqualEqual, TT::Equal),
             '<' => self.add_token_if('=', TT::LessEqual, TT::Less),
             '>' => self.add_token_if('=', TT::GreaterEqual, TT::Greater),
+            '*' => self.add_token_if('*', TT::StarStar, TT::Star),
 
             '/' => self.slash(),
+            '~' => self.tilde(),
             '"' => self.string(),
 
-            ' ' | '\t' => (), '\n' => self.line += 1,
+            ' ' | '\t' => (), '\n' => self.newline(),
 
             d if is_digit(d) => self.number(),
             c if is_alpha(c) => self.identifier(),
 
             _  => {
                 // These characters will be ignored and not passed to the parser.
-                error::scanner_error(self.line, "Unexpected character.");
+                self.error("Unexpected character.");
             }
         }
     }
@@ -96,22 +147,38 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> char {
-        let current = self.current;
+        let current = self.source[self.current];
         self.current += 1;
-        self.source[current]
+        self.current_byte += current.len_utf8();
+        current
     }
 
     fn advance_if(&mut self, expected: char) -> bool {
         if self.is_at_end() { return false; }
         let next: char = self.source[self.current];
         if next != expected { return false; }
-        self.current += 1;
+        self.advance();
         true
     }
 
+    fn newline(&mut self) {
+        // Call after advancing over a line feed.
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
     fn add_token(&mut self, token_type: TT) {
         let lexeme = self.collect_lexeme(self.start, self.current);
-        let new_token = Token::new(token_type, lexeme, self.line);
+
+        // A string literal that spans multiple lines reports the line it ends
+        // on, like the reference implementation, but the column it starts on.
+
+        let new_token = Token::new(
+            token_type, lexeme,
+            self.line, self.column,
+            Span::new(self.start_byte, self.current_byte)
+        );
+
         self.tokens.push(new_token);
     }
 
@@ -128,29 +195,164 @@ impl Scanner {
             while self.peek() != '\n' && !self.is_at_end() {
                 self.advance();
             }
+        } else if self.advance_if('*') {
+            self.block_comment();
         } else {
             self.add_token(TT::Slash);
         }
     }
 
+    fn tilde(&mut self) {
+        // A tilde is only ever the start of integer division.
+
+        if self.advance_if('/') {
+            self.add_token(TT::TildeSlash);
+        } else {
+            self.error("Unexpected character.");
+        }
+    }
+
+    fn block_comment(&mut self) {
+        // Call after advancing over the opening '/*'. Block comments nest, so
+        // commenting out code that has one in it still works.
+
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.is_at_end() {
+                self.error("Unterminated block comment.");
+                self.unterminated = true;
+                return;
+            }
+
+            match self.advance() {
+                '/' if self.advance_if('*') => depth += 1,
+                '*' if self.advance_if('/') => depth -= 1,
+                '\n' => self.newline(),
+                _ => (),
+            }
+        }
+    }
+
+    fn left_brace(&mut self) {
+        // Count the braces inside an interpolation so that a block or a map
+        // literal doesn't end it early.
+
+        if let Some(depth) = self.interpolations.last_mut() {
+            *depth += 1;
+        }
+
+        self.add_token(TT::LeftBrace);
+    }
+
+    fn right_brace(&mut self) {
+        match self.interpolations.last_mut() {
+            Some(0) => {
+                // The interpolation is over and the string picks up again.
+                self.interpolations.pop();
+
+                self.string();
+            },
+            Some(depth) => {
+                *depth -= 1;
+                self.add_token(TT::RightBrace);
+            },
+            None => self.add_token(TT::RightBrace),
+        }
+    }
+
     fn string(&mut self) {
+        // A string starts after its opening quote, or after the brace that
+        // closes an interpolation. Either way the delimiter is one character.
+
+        let mut string = String::new();
+
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' { self.line += 1; }
-            self.advance();
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+                self.add_token(TT::Interpolation(string));
+                self.interpolations.push(0);
+                return;
+            }
+
+            match self.advance() {
+                '\\' => if let Some(escaped) = self.escape() {
+                    string.push(escaped);
+                },
+                '\n' => {
+                    self.newline();
+                    string.push('\n');
+                },
+                character => string.push(character),
+            }
         }
 
         if self.is_at_end() {
-            error::scanner_error(self.line, "Unterminated string.");
-            self.stumbled = true;
+            self.error("Unterminated string.");
+            self.unterminated = true;
             return;
         }
 
         self.advance();
 
-        let string = self.collect_lexeme(self.start + 1, self.current - 1);
         self.add_token(TT::String(string));
     }
 
+    fn escape(&mut self) -> Option<char> {
+        // Call after advancing over a backslash. An invalid escape is reported
+        // where it is, not where its string starts, and left out of the string.
+
+        if self.is_at_end() { return None; }
+
+        let column = self.current - self.line_start;
+        let start_byte = self.current_byte - 1;
+
+        let escaped = match self.advance() {
+            'n'  => Ok('\n'),
+            't'  => Ok('\t'),
+            '\\' => Ok('\\'),
+            '"'  => Ok('"'),
+            '$'  => Ok('$'),
+            'u'  => self.unicode_escape(),
+            '\n' => {
+                self.newline();
+                Err("Invalid escape sequence.")
+            },
+            _ => Err("Invalid escape sequence."),
+        };
+
+        escaped.map_err(|message| {
+            let span = Span::new(start_byte, self.current_byte);
+            let diagnostic = Diagnostic::scanner_error(self.line, column, span, message);
+            self.diagnostics.push(diagnostic);
+        }).ok()
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, &'static str> {
+        // A code point in hexadecimal between braces, like \u{1F96F}.
+
+        if !self.advance_if('{') {
+            return Err("Expect '{' after '\\u'.");
+        }
+
+        let mut digits = String::new();
+
+        while self.peek().is_ascii_hexdigit() {
+            digits.push(self.advance());
+        }
+
+        if !self.advance_if('}') {
+            return Err("Expect '}' after Unicode escape.");
+        }
+
+        let code_point = if digits.len() <= 6 {
+            u32::from_str_radix(&digits, 16).ok()
+        } else { None };
+
+        code_point.and_then(char::from_u32).ok_or("Invalid Unicode code point.")
+    }
+
     fn number(&mut self) {
         while is_digit(self.peek()) { self.advance(); }
        
@@ -166,11 +368,7 @@ impl Scanner {
         match maybe_number {
             Ok(number) => self.add_token(TT::Number(number)),
             Err(_) => {
-                error::scanner_error(
-                    self.line,
-                    "Number cannot be represented with 64 bits."
-                );
-                self.stumbled = true;
+                self.error("Number cannot be represented with 64 bits.");
             }
         }
     }
@@ -186,28 +384,43 @@ impl Scanner {
         // to allocate it statically.
 
         let token = match identifier.as_str() {
-            "and"    => TT::And,
-            "class"  => TT::Class,
-            "else"   => TT::Else,
-            "false"  => TT::False,
-            "for"    => TT::For,
-            "fun"    => TT::Fun,
-            "if"     => TT::If,
-            "nil"    => TT::Nil,
-            "or"     => TT::Or,
-            "print"  => TT::Print,
-            "return" => TT::Return,
-            "super"  => TT::Super(self.new_key()),
-            "this"   => TT::This(self.new_key()),
-            "true"   => TT::True,
-            "var"    => TT::Var,
-            "while"  => TT::While,
-            _        => TT::Identifier(self.new_key(), identifier),
+            "and"      => TT::And,
+            "break"    => TT::Break,
+            "catch"    => TT::Catch,
+            "class"    => TT::Class,
+            "continue" => TT::Continue,
+            "else"     => TT::Else,
+            "false"    => TT::False,
+            "finally"  => TT::Finally,
+            "for"      => TT::For,
+            "fun"      => TT::Fun,
+            "if"       => TT::If,
+            "import"   => TT::Import,
+            "nil"      => TT::Nil,
+            "or"       => TT::Or,
+            "print"    => TT::Print,
+            "return"   => TT::Return,
+            "super"    => TT::Super(self.new_key()),
+            "this"     => TT::This(self.new_key()),
+            "throw"    => TT::Throw,
+            "true"     => TT::True,
+            "try"      => TT::Try,
+            "var"      => TT::Var,
+            "while"    => TT::While,
+            _          => TT::Identifier(self.new_key(), identifier),
         };
 
         self.add_token(token);
     }
 
+    fn error(&mut self, message: &str) {
+        let span = Span::new(self.start_byte, self.current_byte);
+        let diagnostic = Diagnostic::scanner_error(
+            self.line, self.column, span, message
+        );
+        self.diagnostics.push(diagnostic);
+    }
+
     fn new_key(&mut self) -> usize {
         let key = self.identifier_key;
         self.identifier_key += 1;
@@ -222,13 +435,25 @@ impl Scanner {
     }
 }
 
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit()
+}
+
+fn is_alpha(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_alpha_numeric(c: char) -> bool {
+    is_alpha(c) || is_digit(c)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn consume_and_peek() {
-        let mut scanner = Scanner::new("eggs");
+        let mut scanner = Scanner::new("eggs", 0);
 
         // Start state is correct.
 
@@ -275,18 +500,66 @@ mod tests {
         assert_eq!(scanner.peek(), '\0');
         assert_eq!(scanner.peek_next(), '\0');
     }
-}
 
-fn is_digit(c: char) -> bool {
-    c.is_ascii_digit()
-}
+    #[test]
+    fn columns_and_spans() {
+        let source = "var crepe = \"é\";\n  print crepe;";
+        let mut scanner = Scanner::new(source, 0);
+        scanner.scan_tokens();
+        let (tokens, _) = scanner.consume();
+
+        let positions: Vec<(&str, usize, usize, &str)> = tokens.iter().map(
+            |token| (
+                token.lexeme.as_str(), token.line, token.column,
+                &source[token.span.start..token.span.end]
+            )
+        ).collect();
+
+        assert_eq!(positions, vec![
+            ("var",     1, 1,  "var"),
+            ("crepe",   1, 5,  "crepe"),
+            ("=",       1, 11, "="),
+            ("\"é\"", 1, 13, "\"é\""),
+            (";",       1, 16, ";"),
+            ("print",   2, 3,  "print"),
+            ("crepe",   2, 9,  "crepe"),
+            (";",       2, 14, ";"),
+            ("\0",     2, 15, ""),
+        ]);
+    }
 
-fn is_alpha(c: char) -> bool {
-    c.is_ascii_alphabetic() || c == '_'
-}
+    #[test]
+    fn split_interpolations() {
+        let mut scanner = Scanner::new("\"a ${ {} } b ${c}\"", 0);
+        scanner.scan_tokens();
+        let (tokens, diagnostics) = scanner.consume();
+
+        let types: Vec<TT> = tokens.into_iter().map(|token| token.token_type).collect();
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(types, vec![
+            TT::Interpolation("a ".to_string()),
+            TT::LeftBrace,
+            TT::RightBrace,
+            TT::Interpolation(" b ".to_string()),
+            TT::Identifier(0, "c".to_string()),
+            TT::String("".to_string()),
+            TT::EndOfFile,
+        ]);
+    }
 
-fn is_alpha_numeric(c: char) -> bool {
-    is_alpha(c) || is_digit(c)
+    #[test]
+    fn point_at_escapes() {
+        let source = "\"a\\tb\n  \\q\"";
+        let mut scanner = Scanner::new(source, 0);
+        scanner.scan_tokens();
+        let (tokens, diagnostics) = scanner.consume();
+
+        assert_eq!(tokens[0].token_type, TT::String("a\tb\n  ".to_string()));
+        assert_eq!(diagnostics[0].message, "Invalid escape sequence.");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 3));
+        assert_eq!(&source[diagnostics[0].span.start..diagnostics[0].span.end], "\\q");
+    }
 }
 
 // [1]
@@ -0,0 +1,595 @@
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::callable::{Arity, Native};
+use crate::error::{self, Diagnostics, Style};
+use crate::interpreter::Interpreter;
+use crate::list::List;
+use crate::module;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::prelude::Library;
+use crate::scanner::Scanner;
+
+// A session owns one interpreter for its whole lifetime, so globals defined by
+// one call to run() are visible to the next. The prompt runs every line in the
+// same session, and a host program can embed Lox by holding on to one.
+
+pub struct Session {
+    interpreter: Interpreter,
+    stderr: Box<dyn Write>,
+    style: Style,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        // Start with the standard library, except for input and output. See
+        // prelude.rs.
+        Session::with_interpreter(Interpreter::new())
+    }
+
+    pub fn bare() -> Session {
+        // Start without any natives, for a host that picks its own.
+        Session::with_interpreter(Interpreter::bare())
+    }
+
+    fn with_interpreter(interpreter: Interpreter) -> Session {
+        Session {
+            interpreter,
+            stderr: Box::new(io::stderr()),
+            style: Style::Plain,
+        }
+    }
+
+    pub fn load(&mut self, library: Library) {
+        // Define a group of natives from the standard library as globals.
+        self.interpreter.load(library);
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        // The arguments a script sees through the 'args' native.
+
+        let native = Native::new("args", Arity::Fixed(0), move |_| {
+            let args = args.iter().map(|arg| Object::String(arg.clone()));
+            Ok(Object::List(List::new(args.collect())))
+        });
+
+        self.interpreter.define_native(native);
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        // The code a script passed to 'exit' during the last run, if it did.
+        self.interpreter.exit_code()
+    }
+
+    pub fn set_path<P: AsRef<Path>>(&mut self, path: P) {
+        // The file the source comes from. Imports are relative to it, or to the
+        // working directory without one.
+        self.interpreter.set_path(path.as_ref());
+    }
+
+    pub fn set_stdout<W: Write + 'static>(&mut self, sink: W) {
+        // Where print statements write to. Standard output by default.
+        self.interpreter.set_stdout(Box::new(sink));
+    }
+
+    pub fn set_stdin<R: BufRead + 'static>(&mut self, source: R) {
+        // Where 'readLine' and 'input' read from. Standard input by default.
+        self.interpreter.set_stdin(Box::new(source));
+    }
+
+    pub fn set_stderr<W: Write + 'static>(&mut self, sink: W) {
+        // Where scanner, parser, resolver, and runtime errors are reported.
+        // Standard error by default. They're also returned to the caller.
+        self.stderr = Box::new(sink);
+    }
+
+    pub fn set_style(&mut self, style: Style) {
+        // How errors are written to standard error. See error.rs.
+        self.style = style;
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        // How deep calls can nest before a script fails with a stack overflow.
+        // Lower it for a host thread with a small stack. See interpreter.rs.
+        self.interpreter.set_max_depth(max_depth);
+    }
+
+    pub fn define_native<F>(&mut self, name: &str, arity: Arity, function: F)
+        where F: Fn(Vec<Object>) -> Result<Object, String> + 'static
+    {
+        // Expose a Rust closure to scripts as a global function. The
+        // interpreter checks a fixed arity before the closure is called.
+
+        self.interpreter.define_native(Native::new(name, arity, function));
+    }
+
+    pub fn is_unfinished(&self, source: &str) -> bool {
+        // Scan and parse the source without reporting errors.
+
+        let mut scanner = Scanner::new(source, self.interpreter.identifier_key());
+        scanner.scan_tokens();
+
+        if scanner.is_unfinished() { return true; }
+
+        let (tokens, _) = scanner.consume();
+        let mut parser = Parser::new(tokens);
+        parser.parse();
+        parser.is_unfinished()
+    }
+
+    pub fn run_source(&mut self, source: &str) -> Result<Object, Diagnostics> {
+        // Run a program. Its value is the value of its trailing expression
+        // statement, or nil if it doesn't end with one.
+
+        let object = self.run(source)?;
+        Ok(object.unwrap_or(Object::Nil))
+    }
+
+    pub fn eval_expression(&mut self, source: &str) -> Result<Object, Diagnostics> {
+        let object = self.run_with(source, Parser::parse_expression)?;
+
+        // An expression that calls 'exit' doesn't have a value. The caller can
+        // tell from exit_code().
+        Ok(object.unwrap_or(Object::Nil))
+    }
+
+    pub(crate) fn run(&mut self, source: &str) -> Result<Option<Object>, Diagnostics> {
+        self.run_with(source, Parser::parse)
+    }
+
+    fn run_with<P>(
+        &mut self,
+        source: &str, parse: P
+    ) -> Result<Option<Object>, Diagnostics>
+        where P: FnOnce(&mut Parser)
+    {
+        let result = self.pipeline(source, parse);
+
+        if let Err(ref diagnostics) = result {
+            for diagnostic in diagnostics {
+                // A diagnostic from an imported file is shown against it.
+                let module = diagnostic.file.as_deref().and_then(
+                    |file| self.interpreter.source(file)
+                );
+
+                let source = module.as_deref().unwrap_or(source);
+                let rendered = error::render(diagnostic, source, self.style);
+
+                // See error.rs. Ignore a broken sink.
+                let _ = writeln!(self.stderr, "{}", rendered);
+            }
+        }
+
+        result
+    }
+
+    fn pipeline<P>(
+        &mut self,
+        source: &str, parse: P
+    ) -> Result<Option<Object>, Diagnostics>
+        where P: FnOnce(&mut Parser)
+    {
+        // The source being run is file zero. See token.rs.
+
+        let mut identifier_key = self.interpreter.identifier_key();
+        let compiled = module::compile(source, 0, &mut identifier_key, parse);
+        self.interpreter.set_identifier_key(identifier_key);
+        let (statements, resolutions) = compiled?;
+
+        self.interpreter.resolve(resolutions);
+        self.interpreter.interpret(statements)
+    }
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::error::{Diagnostic, Frame, Location, Phase};
+    use crate::token::Span;
+
+    fn phase(result: Result<Object, Diagnostics>) -> Option<Phase> {
+        result.err().map(|diagnostics| diagnostics[0].phase)
+    }
+
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unfinished_source() {
+        let session = Session::new();
+
+        assert!(session.is_unfinished("fun add(a, b) {"));
+        assert!(session.is_unfinished("print (1 +"));
+        assert!(session.is_unfinished("var greeting = \"hello"));
+        assert!(session.is_unfinished("var x ="));
+        assert!(session.is_unfinished("/* a comment"));
+
+        assert!(!session.is_unfinished("var x = 1;"));
+        assert!(!session.is_unfinished("var = 1;"));
+        assert!(!session.is_unfinished("print (1 + 2));"));
+    }
+
+    #[test]
+    fn keep_globals() {
+        let mut session = Session::new();
+
+        assert_eq!(session.run_source("var x = 2;"), Ok(Object::Nil));
+        assert_eq!(session.run_source("fun twice(n) { return 2 * n; }"), Ok(Object::Nil));
+        assert_eq!(session.run_source("twice(x);"), Ok(Object::Number(4.0)));
+        assert_eq!(session.eval_expression("twice(x) + 1"), Ok(Object::Number(5.0)));
+    }
+
+    #[test]
+    fn call_natives() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+
+        session.define_native("sum", Arity::Variadic, |arguments| {
+            let mut sum = 0.0;
+
+            for argument in arguments {
+                match argument {
+                    Object::Number(number) => sum += number,
+                    _ => return Err("Arguments must be numbers.".to_string()),
+                }
+            }
+
+            Ok(Object::Number(sum))
+        });
+
+        session.define_native("double", Arity::Fixed(1), |arguments| {
+            match arguments[0] {
+                Object::Number(number) => Ok(Object::Number(2.0 * number)),
+                _ => Err("Argument must be a number.".to_string()),
+            }
+        });
+
+        assert_eq!(session.eval_expression("sum()"), Ok(Object::Number(0.0)));
+        assert_eq!(session.eval_expression("sum(1, 2, 3)"), Ok(Object::Number(6.0)));
+        assert_eq!(session.eval_expression("double(sum(1, 2))"), Ok(Object::Number(6.0)));
+
+        assert_eq!(phase(session.eval_expression("sum(1, nil)")), Some(Phase::Interpret));
+        assert_eq!(phase(session.eval_expression("double(1, 2)")), Some(Phase::Interpret));
+    }
+
+    #[test]
+    fn redirect_output() {
+        let stdout = Buffer::default();
+        let stderr = Buffer::default();
+
+        let mut session = Session::new();
+        session.set_stdout(stdout.clone());
+        session.set_stderr(stderr.clone());
+
+        let _ = session.run_source("print \"lox\"; print 1 + 2;");
+        let _ = session.run_source("print;");
+        let _ = session.run_source("print -nil;");
+
+        assert_eq!(stdout.contents(), "lox\n3\n");
+        assert_eq!(
+            stderr.contents(),
+            "[line 1] Error at ';': Expect expression.\n\
+             Operand must be a number.\n[line 1]\n"
+        );
+    }
+
+    #[test]
+    fn render_snippets() {
+        let stderr = Buffer::default();
+
+        let mut session = Session::new();
+        session.set_stderr(stderr.clone());
+        session.set_style(Style::Rich);
+
+        let _ = session.run_source("{\n  var a = 1;\n\tvar a = 2;\n}");
+        let _ = session.run_source("print -\"crepe\";");
+
+        assert_eq!(
+            stderr.contents(),
+            "[line 3] Error at 'a': Already a variable with this name in this scope.\n\
+             \x20 |\n\
+             3 | \tvar a = 2;\n\
+             \x20 | \t    ^\n\
+             \x20 |\n\
+             2 |   var a = 1;\n\
+             \x20 |       - previous declaration here\n\
+             Operand must be a number.\n\
+             [line 1]\n\
+             \x20 |\n\
+             1 | print -\"crepe\";\n\
+             \x20 |       ^\n"
+        );
+    }
+
+    #[test]
+    fn report_phase() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+
+        assert_eq!(phase(session.run_source("\"cheese")), Some(Phase::Scan));
+        assert_eq!(phase(session.run_source("var;")), Some(Phase::Parse));
+        assert_eq!(phase(session.run_source("return 1;")), Some(Phase::Resolve));
+        assert_eq!(phase(session.run_source("-\"cheese\";")), Some(Phase::Interpret));
+        assert_eq!(phase(session.eval_expression("1 + 2;")), Some(Phase::Parse));
+    }
+
+    #[test]
+    fn collect_diagnostics() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+
+        let diagnostics = session.run_source("var a = @;\nprint;\nfun f() {").unwrap_err();
+
+        let semicolon = || Location::Lexeme(";".to_string());
+
+        assert_eq!(diagnostics, vec![
+            Diagnostic::new(
+                Phase::Scan, "Unexpected character.",
+                Location::Line, 1, 9, Span::new(8, 9)
+            ),
+            Diagnostic::new(
+                Phase::Parse, "Expect expression.",
+                semicolon(), 1, 10, Span::new(9, 10)
+            ),
+            Diagnostic::new(
+                Phase::Parse, "Expect expression.",
+                semicolon(), 2, 6, Span::new(16, 17)
+            ),
+            Diagnostic::new(
+                Phase::Parse, "Expect '}' after block.",
+                Location::End, 3, 10, Span::new(27, 27)
+            ),
+        ]);
+    }
+
+    #[test]
+    fn trace_calls() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+
+        let source = "class Bagel {\n  init() { toast(); }\n}\n\
+                      fun toast() {\n  nil();\n}\nBagel();";
+
+        let diagnostics = session.run_source(source).unwrap_err();
+
+        assert_eq!(diagnostics[0].trace, vec![
+            Frame::new("toast()", 5),
+            Frame::new("init()", 2),
+            Frame::new("script", 7),
+        ]);
+
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "Can only call functions and classes.\n\
+             [line 5] in toast()\n[line 2] in init()\n[line 7] in script"
+        );
+
+        // The call stack is empty again for the next run.
+        let diagnostics = session.run_source("nil();").unwrap_err();
+        assert!(diagnostics[0].trace.is_empty());
+
+        // Even when a finally block swallowed an error in a function.
+        session.run_source(
+            "fun swallow() { try { nil(); } finally { return; } } swallow();"
+        ).unwrap();
+
+        let diagnostics = session.run_source("nil();").unwrap_err();
+        assert!(diagnostics[0].trace.is_empty());
+    }
+
+    #[test]
+    fn overflow_stack() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+        session.set_max_depth(32);
+
+        let diagnostics = session.run_source(
+            "fun dig(n) {\n  return dig(n + 1);\n}\ndig(0);"
+        ).unwrap_err();
+
+        assert_eq!(diagnostics[0].message, "Stack overflow.");
+        assert_eq!(diagnostics[0].phase.exit_code(), 70);
+        assert_eq!(diagnostics[0].trace.len(), 33);
+
+        assert!(diagnostics[0].to_string().starts_with(
+            "Stack overflow.\n[line 2] in dig()\n[line 2] in dig()\n"
+        ));
+
+        // The session recovers and calls work again.
+        assert_eq!(
+            session.run_source("fun id(n) { return n; }\nid(7);"),
+            Ok(Object::Number(7.0))
+        );
+    }
+
+    #[test]
+    fn load_libraries() {
+        let mut session = Session::bare();
+        session.set_stderr(io::sink());
+
+        assert_eq!(phase(session.run_source("clock();")), Some(Phase::Interpret));
+        assert_eq!(phase(session.run_source("sqrt(4);")), Some(Phase::Interpret));
+
+        session.load(Library::Math);
+
+        assert_eq!(session.eval_expression("sqrt(4)"), Ok(Object::Number(2.0)));
+        assert_eq!(phase(session.run_source("clock();")), Some(Phase::Interpret));
+    }
+
+    #[test]
+    fn exit_early() {
+        let mut session = Session::new();
+        let stdout = Buffer::default();
+        session.set_stdout(stdout.clone());
+        session.set_stderr(io::sink());
+
+        // Input and output are opt in.
+        assert_eq!(phase(session.run_source("exit(3);")), Some(Phase::Interpret));
+
+        session.load(Library::Io);
+
+        assert_eq!(session.run_source("print 1; exit(3); print 2;"), Ok(Object::Nil));
+        assert_eq!(session.exit_code(), Some(3));
+        assert_eq!(stdout.contents(), "1\n");
+
+        // A function can't catch an exit on its way out.
+        session.run_source("fun f() { exit(4); }\nf();").unwrap();
+        assert_eq!(session.exit_code(), Some(4));
+
+        session.run_source("1;").unwrap();
+        assert_eq!(session.exit_code(), None);
+    }
+
+    #[test]
+    fn exit_from_expression() {
+        let mut session = Session::new();
+        session.load(Library::Io);
+
+        assert_eq!(session.eval_expression("exit(0)"), Ok(Object::Nil));
+        assert_eq!(session.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn read_supplied_input() {
+        let mut session = Session::new();
+        session.load(Library::Io);
+        session.set_stdin(io::Cursor::new("plain\r\nsesame"));
+
+        assert_eq!(session.eval_expression("readLine()"), Ok(Object::String("plain".to_string())));
+        assert_eq!(session.eval_expression("input()"), Ok(Object::String("sesame".to_string())));
+        assert_eq!(session.eval_expression("readLine()"), Ok(Object::Nil));
+    }
+
+    #[test]
+    fn read_and_write_files() {
+        let mut session = Session::new();
+        session.set_stderr(io::sink());
+        session.load(Library::Io);
+        session.set_args(vec!["bagel".to_string()]);
+
+        let path = std::env::temp_dir().join(format!("rlox-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap().replace('\\', "/");
+
+        session.run_source(&format!("var path = \"{}\";", path)).unwrap();
+        session.run_source("writeFile(path, \"plain \");").unwrap();
+        session.run_source("appendFile(path, args()[0]);").unwrap();
+
+        assert_eq!(session.eval_expression("fileExists(path)"), Ok(Object::Boolean(true)));
+        assert_eq!(
+            session.eval_expression("readFile(path)"),
+            Ok(Object::String("plain bagel".to_string()))
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn import_modules() {
+        let directory = std::env::temp_dir().join(format!("rlox-modules-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let write = |name: &str, source: &str| {
+            std::fs::write(directory.join(name), source).unwrap();
+        };
+
+        write("main.lox", "");
+        write("a.lox", "import b;");
+        write("b.lox", "import a;");
+        write("broken.lox", "var;\nprint;");
+        write("fails.lox", "var x = 1;\nnil();");
+        write("later.lox", "fun fail() {\n  return -\"scone\";\n}");
+
+        let stderr = Buffer::default();
+
+        let mut session = Session::new();
+        session.set_stderr(stderr.clone());
+        session.set_path(directory.join("main.lox"));
+
+        // Outside of the working directory, a module is named by its full path.
+        let name = |file: &str| {
+            let directory = std::fs::canonicalize(&directory).unwrap();
+            directory.join(file).display().to_string()
+        };
+
+        let message = |session: &mut Session, source: &str| {
+            session.run_source(source).unwrap_err()[0].message.clone()
+        };
+
+        assert_eq!(
+            message(&mut session, "import a;"),
+            "Can't import 'a.lox': cycle a.lox -> b.lox -> a.lox."
+        );
+        assert!(
+            message(&mut session, "import missing;").starts_with("Can't import 'missing.lox': ")
+        );
+
+        // A module that doesn't parse reports every error in its own file.
+        let diagnostics = session.run_source("import broken;").unwrap_err();
+        assert_eq!(diagnostics, vec![
+            Diagnostic::new(
+                Phase::Parse, "Expect variable name.",
+                Location::Lexeme(";".to_string()), 1, 4, Span::new(3, 4)
+            ).in_file(&name("broken.lox")),
+            Diagnostic::new(
+                Phase::Parse, "Expect expression.",
+                Location::Lexeme(";".to_string()), 2, 6, Span::new(10, 11)
+            ).in_file(&name("broken.lox")),
+        ]);
+
+        // A runtime error names the module in its trace.
+        let diagnostics = session.run_source("import fails;").unwrap_err();
+        assert_eq!(diagnostics[0].file, Some(name("fails.lox")));
+        assert_eq!(diagnostics[0].trace, vec![
+            Frame::new("module fails.lox", 2).in_file(&name("fails.lox")),
+            Frame::new("script", 1),
+        ]);
+
+        // Even once the module has finished running.
+        session.set_style(Style::Rich);
+        stderr.0.borrow_mut().clear();
+
+        let diagnostics = session.run_source("import later;\n\nlater.fail();");
+        let diagnostic = &diagnostics.unwrap_err()[0];
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 10));
+        assert_eq!(diagnostic.file, Some(name("later.lox")));
+
+        assert_eq!(stderr.contents(), format!(
+            "Operand must be a number.\n\
+             [line 2 of {0}] in fail()\n\
+             [line 3] in script\n  \
+               |\n\
+             2 |   return -\"scone\";\n  \
+               |          ^\n",
+            name("later.lox")
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
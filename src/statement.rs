@@ -1,56 +1,104 @@
 use crate::callable::definitions as def;
 use crate::expression::Expr;
-use crate::token::Token;
+use crate::token::{Span, Token};
+
+// Every statement keeps the bytes of source it was parsed from, keywords and
+// punctuation included. A desugared 'for' loop spans the whole loop.
 
 #[derive(Debug)]
 pub enum Stmt {
-    Block(Vec<Stmt>),
-    Class(def::Class),
-    Expression(Expr),
-    Function(def::Function),
-    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    Print(Expr),
-    Return(Token, Option<Expr>),
-    Var(Token, Option<Expr>),
-    While(Expr, Box<Stmt>),
+    Block(Span, Vec<Stmt>),
+    Break(Span, Token),
+    Class(Span, def::Class),
+    Continue(Span, Token),
+    Expression(Span, Expr),
+    Function(Span, def::Function),
+    If(Span, Expr, Box<Stmt>, Option<Box<Stmt>>),
+    Import(Span, Token, String, Token),
+    Print(Span, Expr),
+    Return(Span, Token, Option<Expr>),
+    Throw(Span, Token, Expr),
+    Try(Span, Vec<Stmt>, Option<(Token, Vec<Stmt>)>, Option<Vec<Stmt>>),
+    Var(Span, Token, Option<Expr>),
+    While(Span, Expr, Box<Stmt>, Option<Expr>),
 }
 
 pub trait Visitor<T> {
     fn visit_block(&mut self, statements: &[Stmt]) -> T;
+    fn visit_break(&mut self, keyword: &Token) -> T;
     fn visit_class(&mut self, definition: &def::Class) -> T;
+    fn visit_continue(&mut self, keyword: &Token) -> T;
     fn visit_expression(&mut self, expression: &Expr) -> T;
     fn visit_function(&mut self, definition: &def::Function) -> T;
     fn visit_if(
         &mut self, condition: &Expr,
         then_branch: &Stmt, else_branch: &Option<Box<Stmt>>
     ) -> T;
+    fn visit_import(&mut self, keyword: &Token, path: &str, name: &Token) -> T;
     fn visit_print(&mut self, object: &Expr) -> T;
     fn visit_return(&mut self, keyword: &Token, object: &Option<Expr>) -> T;
+    fn visit_throw(&mut self, keyword: &Token, object: &Expr) -> T;
+    fn visit_try(
+        &mut self, body: &[Stmt],
+        catch: &Option<(Token, Vec<Stmt>)>, finally: &Option<Vec<Stmt>>
+    ) -> T;
     fn visit_var(&mut self, name: &Token, object: &Option<Expr>) -> T;
-    fn visit_while(&mut self, condition: &Expr, body: &Stmt) -> T;
+    fn visit_while(
+        &mut self, condition: &Expr,
+        body: &Stmt, increment: &Option<Expr>
+    ) -> T;
 }
 
 impl Stmt {
     pub fn accept<T>(&self, visitor: &mut impl Visitor<T>) -> T {
         match self {
-            Stmt::Block(statements) =>
+            Stmt::Block(_, statements) =>
                 visitor.visit_block(statements),
-            Stmt::Class(definition) =>
+            Stmt::Break(_, keyword) =>
+                visitor.visit_break(keyword),
+            Stmt::Class(_, definition) =>
                 visitor.visit_class(definition),
-            Stmt::Expression(expression) =>
+            Stmt::Continue(_, keyword) =>
+                visitor.visit_continue(keyword),
+            Stmt::Expression(_, expression) =>
                 visitor.visit_expression(expression),
-            Stmt::Function(definition) =>
+            Stmt::Function(_, definition) =>
                 visitor.visit_function(definition),
-            Stmt::If(condition, then_branch, else_branch) =>
+            Stmt::If(_, condition, then_branch, else_branch) =>
                 visitor.visit_if(condition, then_branch, else_branch),
-            Stmt::Print(object) =>
+            Stmt::Import(_, keyword, path, name) =>
+                visitor.visit_import(keyword, path, name),
+            Stmt::Print(_, object) =>
                 visitor.visit_print(object),
-            Stmt::Return(keyword, object) =>
+            Stmt::Return(_, keyword, object) =>
                 visitor.visit_return(keyword, object),
-            Stmt::Var(name, object) =>
+            Stmt::Throw(_, keyword, object) =>
+                visitor.visit_throw(keyword, object),
+            Stmt::Try(_, body, catch, finally) =>
+                visitor.visit_try(body, catch, finally),
+            Stmt::Var(_, name, object) =>
                 visitor.visit_var(name, object),
-            Stmt::While(condition, body) =>
-                visitor.visit_while(condition, body),
+            Stmt::While(_, condition, body, increment) =>
+                visitor.visit_while(condition, body, increment),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block(span, ..)
+                | Stmt::Break(span, ..)
+                | Stmt::Class(span, ..)
+                | Stmt::Continue(span, ..)
+                | Stmt::Expression(span, ..)
+                | Stmt::Function(span, ..)
+                | Stmt::If(span, ..)
+                | Stmt::Import(span, ..)
+                | Stmt::Print(span, ..)
+                | Stmt::Return(span, ..)
+                | Stmt::Throw(span, ..)
+                | Stmt::Try(span, ..)
+                | Stmt::Var(span, ..)
+                | Stmt::While(span, ..) => *span,
         }
     }
 }
@@ -1,15 +1,42 @@
 use crate::token_type::TokenType as TT;
 
+// A half-open range of byte offsets into the source.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+// The file a token came from is zero for the source being run and counts up
+// from one for the files it imports, in the order they were first read.
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TT,
     pub lexeme: String,
     pub line: usize,
+    pub column: usize,
+    pub span: Span,
+    pub file: usize,
 }
 
 impl Token {
-    pub fn new(token_type: TT, lexeme: String, line: usize) -> Token {
-        Token { token_type, lexeme, line }
+    pub fn new(
+        token_type: TT, lexeme: String,
+        line: usize, column: usize, span: Span
+    ) -> Token {
+        Token { token_type, lexeme, line, column, span, file: 0 }
     }
 
     pub fn to_name(&self) -> (&usize, &str) {
@@ -1,21 +1,25 @@
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenType {
     // Single-character tokens.
-    LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+    Colon, Comma, Dot, Minus, Percent, Plus, Semicolon, Slash, Star,
 
     // One- or two-character tokens.
     Bang, BangEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
     Less, LessEqual,
+    StarStar,
+    TildeSlash,
 
-    // Literals.
-    Identifier(usize, String), String(String), Number(f64),
+    // Literals. A string with interpolations is split into segments, each
+    // ending where an interpolation begins, and then a final string.
+    Identifier(usize, String), String(String), Interpolation(String), Number(f64),
 
     // Keywords.
-    And, Class, Else, False, Fun, For, If, Nil, Or,
-    Print, Return, Super(usize), This(usize), True, Var, While,
+    And, Break, Catch, Class, Continue, Else, False, Finally, Fun, For, If,
+    Import, Nil, Or, Print, Return, Super(usize), This(usize), Throw, True, Try,
+    Var, While,
 
     EndOfFile,
 }